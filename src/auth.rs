@@ -0,0 +1,511 @@
+//! The different ways of obtaining an intranet session cookie.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use reqwest::{header, Url};
+
+use crate::error::Error;
+use crate::serde_utils::decode_entities;
+use crate::session;
+use crate::transport::{Request, Transport};
+
+/// The maximum number of redirections followed during an authentication flow.
+const MAX_REDIRECTS: usize = 10;
+
+/// A session cookie, as obtained by an `Authenticator`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    /// The value of the `user` cookie.
+    pub cookie: String,
+    /// The expiration date of the cookie, if announced by the intranet.
+    pub expires: Option<SystemTime>,
+}
+
+/// A method of obtaining a session cookie for the intranet.
+///
+/// The authenticator given to `ClientBuilder::authenticator` is kept by the client,
+/// which uses it again to re-authenticate once its session cookie expires.
+/// Its requests go through the client's transport, which does not follow redirections,
+/// and `endpoint` is the base URL of the intranet the client targets (see `ClientBuilder::endpoint`).
+#[async_trait]
+pub trait Authenticator: fmt::Debug + Send + Sync {
+    async fn authenticate(
+        &self,
+        transport: &dyn Transport,
+        endpoint: &str,
+    ) -> Result<Credentials, Error>;
+
+    /// The autologin link to store in the sessions created by this authenticator, if any.
+    fn autologin(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether calling `authenticate` again can yield a fresh cookie once the current one expires.
+    fn renewable(&self) -> bool {
+        true
+    }
+}
+
+/// Authenticates by replaying an autologin link (eg. `https://intra.epitech.eu/auth-0123456789abcdef`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutologinAuthenticator {
+    autologin: String,
+}
+
+impl AutologinAuthenticator {
+    pub fn new<T: Into<String>>(autologin: T) -> AutologinAuthenticator {
+        AutologinAuthenticator {
+            autologin: autologin.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Authenticator for AutologinAuthenticator {
+    async fn authenticate(&self, transport: &dyn Transport, _: &str) -> Result<Credentials, Error> {
+        let response = transport
+            .send(Request::new("GET", self.autologin.as_str()))
+            .await?;
+        let (cookie, expires) =
+            session::extract_cookie(response.header_values(header::SET_COOKIE.as_str()))?;
+        Ok(Credentials { cookie, expires })
+    }
+
+    fn autologin(&self) -> Option<&str> {
+        Some(self.autologin.as_str())
+    }
+}
+
+/// Uses an already obtained `user` cookie (eg. copied from a web browser).
+///
+/// Such a session cannot be renewed once the cookie expires.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CookieAuthenticator {
+    cookie: String,
+}
+
+impl CookieAuthenticator {
+    /// Accepts either the raw value of the cookie or the `user=...` pair.
+    pub fn new<T: Into<String>>(cookie: T) -> CookieAuthenticator {
+        let cookie = cookie.into();
+        let cookie = match cookie.strip_prefix("user=") {
+            Some(value) => value.to_string(),
+            None => cookie,
+        };
+        CookieAuthenticator { cookie }
+    }
+}
+
+impl fmt::Debug for CookieAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CookieAuthenticator").finish()
+    }
+}
+
+#[async_trait]
+impl Authenticator for CookieAuthenticator {
+    async fn authenticate(&self, _: &dyn Transport, _: &str) -> Result<Credentials, Error> {
+        if self.cookie.is_empty() {
+            return Err(Error::CookieNotFound);
+        }
+        Ok(Credentials {
+            cookie: self.cookie.clone(),
+            expires: None,
+        })
+    }
+
+    fn renewable(&self) -> bool {
+        false
+    }
+}
+
+/// Authenticates through the Microsoft (Office365) OAuth flow of the intranet, using a login and a password.
+///
+/// The flow starts at the intranet's `/auth/office365` route, follows the redirections
+/// to the authorization page, and submits the credentials through its login form,
+/// along with the hidden fields of the form (eg. `flowToken`, `ctx` or `canary`).
+/// It then follows the redirections back to the intranet until it hands out the session cookie,
+/// which is only accepted from the host of the client's endpoint.
+#[derive(Clone)]
+pub struct Office365Authenticator {
+    login: String,
+    password: String,
+    login_field: String,
+    password_field: String,
+}
+
+impl Office365Authenticator {
+    pub fn new<L: Into<String>, P: Into<String>>(login: L, password: P) -> Office365Authenticator {
+        Office365Authenticator {
+            login: login.into(),
+            password: password.into(),
+            login_field: String::from("login"),
+            password_field: String::from("passwd"),
+        }
+    }
+
+    /// Sets the names of the form fields holding the credentials (defaults to `login` and `passwd`).
+    #[inline]
+    pub fn form_fields<L: Into<String>, P: Into<String>>(
+        mut self,
+        login_field: L,
+        password_field: P,
+    ) -> Office365Authenticator {
+        self.login_field = login_field.into();
+        self.password_field = password_field.into();
+        self
+    }
+}
+
+impl fmt::Debug for Office365Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Office365Authenticator")
+            .field("login", &self.login)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Authenticator for Office365Authenticator {
+    async fn authenticate(
+        &self,
+        transport: &dyn Transport,
+        endpoint: &str,
+    ) -> Result<Credentials, Error> {
+        let start = parse_url(&format!(
+            "{}/auth/office365",
+            endpoint.trim_end_matches('/')
+        ))?;
+        let mut flow = RedirectFlow {
+            transport,
+            intranet: start.clone(),
+            jar: CookieJar::default(),
+        };
+
+        let (authorize, page) = match flow.follow("GET", start, &[]).await? {
+            FlowStep::Authenticated(credentials) => return Ok(credentials),
+            FlowStep::Landed(url, page) => (url, page),
+        };
+
+        let login_form = LoginForm::parse(&page, &authorize).ok_or_else(|| {
+            Error::ParserError(String::from("no login form on the authorization page"))
+        })?;
+        let mut form: Vec<_> = login_form
+            .fields
+            .iter()
+            .filter(|(name, _)| *name != self.login_field && *name != self.password_field)
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        form.push((self.login_field.as_str(), self.login.as_str()));
+        form.push((self.password_field.as_str(), self.password.as_str()));
+        match flow.follow("POST", login_form.action, &form).await? {
+            FlowStep::Authenticated(credentials) => Ok(credentials),
+            FlowStep::Landed(..) => Err(Error::CookieNotFound),
+        }
+    }
+}
+
+enum FlowStep {
+    Authenticated(Credentials),
+    Landed(Url, String),
+}
+
+/// Follows redirections by hand, keeping track of the cookies set along the way.
+struct RedirectFlow<'a> {
+    transport: &'a dyn Transport,
+    /// A URL of the intranet, the only origin whose session cookie is accepted.
+    intranet: Url,
+    jar: CookieJar,
+}
+
+impl RedirectFlow<'_> {
+    async fn follow(
+        &mut self,
+        method: &str,
+        url: Url,
+        form: &[(&str, &str)],
+    ) -> Result<FlowStep, Error> {
+        let mut method = method;
+        let mut url = url;
+        for _ in 0..MAX_REDIRECTS {
+            let mut request = Request::new(method, url.as_str());
+            if let Some(cookies) = self.jar.header(&url) {
+                request = request.header(header::COOKIE.as_str(), cookies);
+            }
+            if method == "POST" {
+                request = request.form(form);
+            }
+            let response = self.transport.send(request).await?;
+            if url.origin() == self.intranet.origin() {
+                let set_cookies = response.header_values(header::SET_COOKIE.as_str());
+                if let Ok((cookie, expires)) = session::extract_cookie(set_cookies) {
+                    return Ok(FlowStep::Authenticated(Credentials { cookie, expires }));
+                }
+            }
+            self.jar
+                .store(&url, response.header_values(header::SET_COOKIE.as_str()));
+            if !(300..400).contains(&response.status) {
+                if response.is_success() {
+                    return Ok(FlowStep::Landed(url, response.text()));
+                }
+                return Err(Error::from_status(response.status, url.path()));
+            }
+            let location = response
+                .header_value(header::LOCATION.as_str())
+                .ok_or(Error::InvalidStatusCode(response.status))?;
+            url = url.join(location).map_err(|_| Error::InternalError)?;
+            method = "GET";
+        }
+        Err(Error::InternalError)
+    }
+}
+
+/// A cookie stored by a `CookieJar`.
+struct Cookie {
+    name: String,
+    value: String,
+    /// The lowercased domain of the cookie, without its leading dot.
+    domain: String,
+    /// Whether the cookie only goes back to the host that set it (ie. it came without a `Domain` attribute).
+    host_only: bool,
+    path: String,
+}
+
+impl Cookie {
+    fn matches(&self, host: &str, path: &str) -> bool {
+        let domain = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(host, &self.domain)
+        };
+        domain && path_matches(path, &self.path)
+    }
+}
+
+/// The cookies set along an authentication flow.
+///
+/// They are sent back according to their `Domain` and `Path` attributes,
+/// as the Microsoft login pages set cookies shared across their sibling subdomains.
+#[derive(Default)]
+pub(crate) struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Stores the cookies of the `Set-Cookie` headers of a response to `url`.
+    pub(crate) fn store<'a, I>(&mut self, url: &Url, set_cookies: I)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let host = match url.host_str() {
+            Some(host) => host.to_lowercase(),
+            None => return,
+        };
+        for set_cookie in set_cookies {
+            let mut attributes = set_cookie.split(';').map(str::trim);
+            let mut pair = attributes.next().unwrap_or_default().splitn(2, '=');
+            let (name, value) = match (pair.next(), pair.next()) {
+                (Some(name), Some(value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+                _ => continue,
+            };
+
+            let mut domain = None;
+            let mut path = None;
+            let mut max_age = None;
+            let mut expired = false;
+            for attribute in attributes {
+                let mut parts = attribute.splitn(2, '=');
+                let name = parts.next().unwrap_or_default().trim().to_lowercase();
+                let value = parts.next().unwrap_or_default().trim();
+                match name.as_str() {
+                    "domain" if !value.is_empty() => {
+                        domain = Some(value.trim_start_matches('.').to_lowercase());
+                    }
+                    "path" if value.starts_with('/') => path = Some(value.to_string()),
+                    "max-age" => max_age = value.parse::<i64>().ok(),
+                    "expires" => {
+                        let value = value.replace('-', " ");
+                        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(&value) {
+                            expired = SystemTime::from(date) <= SystemTime::now();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // A host can only set cookies for its own domain or one of its parents.
+            if let Some(domain) = &domain {
+                if !domain_matches(&host, domain) {
+                    continue;
+                }
+            }
+
+            let cookie = Cookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                host_only: domain.is_none(),
+                domain: domain.unwrap_or_else(|| host.clone()),
+                path: path.unwrap_or_else(|| default_path(url.path())),
+            };
+            self.cookies.retain(|existing| {
+                existing.name != cookie.name
+                    || existing.domain != cookie.domain
+                    || existing.path != cookie.path
+            });
+            // `Max-Age` takes precedence over `Expires`.
+            if !max_age.map_or(expired, |seconds| seconds <= 0) {
+                self.cookies.push(cookie);
+            }
+        }
+    }
+
+    /// The value of the `Cookie` header to send along a request to `url`, if any cookie matches it.
+    pub(crate) fn header(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?.to_lowercase();
+        let cookies: Vec<_> = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(&host, url.path()))
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        if cookies.is_empty() {
+            None
+        } else {
+            Some(cookies.join("; "))
+        }
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The path of a cookie set without a `Path` attribute: the "directory" of the request path.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => String::from("/"),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+/// The login form of an authorization page.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct LoginForm {
+    /// The URL the form gets submitted to.
+    pub(crate) action: Url,
+    /// The hidden fields of the form (eg. `flowToken`, `ctx` or `canary`), in document order.
+    pub(crate) fields: Vec<(String, String)>,
+}
+
+impl LoginForm {
+    /// Parses the first form of a page, resolving its action against the URL of the page.
+    pub(crate) fn parse(page: &str, url: &Url) -> Option<LoginForm> {
+        let lowercase = page.to_ascii_lowercase();
+        let start = lowercase.find("<form")?;
+        let end = lowercase[start..]
+            .find("</form")
+            .map_or(page.len(), |end| start + end);
+        let form = &page[start..end];
+
+        let form_attributes = attributes(tags(form, "form").first()?);
+        let action = match form_attributes
+            .get("action")
+            .filter(|action| !action.is_empty())
+        {
+            Some(action) => url.join(action).ok()?,
+            None => url.clone(),
+        };
+        let fields = hidden_inputs(form);
+        Some(LoginForm { action, fields })
+    }
+}
+
+/// The names and values of the hidden inputs of an HTML snippet, in document order.
+pub(crate) fn hidden_inputs(html: &str) -> Vec<(String, String)> {
+    tags(html, "input")
+        .into_iter()
+        .map(attributes)
+        .filter(|input| {
+            let kind = input.get("type").map(String::as_str).unwrap_or_default();
+            kind.eq_ignore_ascii_case("hidden")
+        })
+        .filter_map(|mut input| {
+            let name = input.remove("name").filter(|name| !name.is_empty())?;
+            let value = input.remove("value").unwrap_or_default();
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// Finds the tags of the given name in an HTML snippet, returning the text of their attributes.
+fn tags<'a>(html: &'a str, name: &str) -> Vec<&'a str> {
+    let lowercase = html.to_ascii_lowercase();
+    let opening = format!("<{}", name);
+    let mut tags = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = lowercase[offset..].find(&opening) {
+        let start = offset + start + opening.len();
+        let end = match html[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = &html[start..end];
+        if tag.is_empty() || tag.starts_with(|ch: char| ch.is_ascii_whitespace() || ch == '/') {
+            tags.push(tag);
+        }
+        offset = end;
+    }
+    tags
+}
+
+/// Parses the attributes of an HTML tag, lowercasing their names and decoding the entities of their values.
+fn attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|ch: char| ch.is_ascii_whitespace() || ch == '/');
+        if rest.is_empty() {
+            break;
+        }
+        let end = rest
+            .find(|ch: char| ch.is_ascii_whitespace() || ch == '=' || ch == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..end].to_ascii_lowercase();
+        rest = rest[end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                let (value, remaining) = match value.chars().next() {
+                    Some(quote) if quote == '"' || quote == '\'' => {
+                        let value = &value[1..];
+                        let end = value.find(quote).unwrap_or(value.len());
+                        (&value[..end], value.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = value
+                            .find(|ch: char| ch.is_ascii_whitespace())
+                            .unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                rest = remaining;
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        attributes.entry(name).or_insert(value);
+    }
+    attributes
+}
+
+fn parse_url(url: &str) -> Result<Url, Error> {
+    Url::parse(url).map_err(|_| Error::InternalError)
+}
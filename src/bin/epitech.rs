@@ -0,0 +1,689 @@
+//! `epitech`, a command-line client for the EPITECH intranet (requires the `cli` feature).
+//!
+//! The autologin link is read from the `EPITECH_AUTOLOGIN` environment variable or from the
+//! `autologin` field of a JSON configuration file (see `USAGE`).
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error as StdError;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+use chrono::{Datelike, Local};
+use serde::{Deserialize, Serialize};
+
+use epitech_api::blocking::Client;
+use epitech_api::error::Error;
+use epitech_api::response::UserEntry;
+use epitech_api::{Location, Promo};
+
+static USAGE: &str = "\
+Usage: epitech [OPTIONS] <COMMAND>
+
+Commands:
+  whoami                 Shows the authenticated student
+  students               Lists the students [--location CODE] [--promo PROMO] [--year YEAR] [--course CODE]
+  notes [LOGIN]          Lists the marks of a student (defaults to the authenticated one)
+  netsoul [LOGIN]        Lists the daily log time of a student (defaults to the authenticated one)
+  search <TERM>          Searches the students by login
+  courses                Lists the courses of a campus --location CODE [--year YEAR]
+  raw <PATH>             Prints the response body of an arbitrary intranet route
+
+Options:
+  -f, --format FORMAT    Output format: table (default), json or csv
+  -c, --config PATH      Configuration file (defaults to $EPITECH_CONFIG, or epitech/config.json
+                         in $XDG_CONFIG_HOME or ~/.config)
+  -h, --help             Prints this message
+
+The autologin link is read from $EPITECH_AUTOLOGIN, or from the configuration file:
+  { \"autologin\": \"https://intra.epitech.eu/auth-...\" }
+An `endpoint` field (or $EPITECH_ENDPOINT) targets another intranet.
+";
+
+/// The options taking a value, by name, along with their short form.
+static VALUE_OPTIONS: &[(&str, Option<char>)] = &[
+    ("format", Some('f')),
+    ("config", Some('c')),
+    ("location", None),
+    ("promo", None),
+    ("year", None),
+    ("course", None),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Whoami,
+    Students {
+        location: Option<Location>,
+        promo: Option<Promo>,
+        year: Option<u32>,
+        course: Option<String>,
+    },
+    Notes(Option<String>),
+    Netsoul(Option<String>),
+    Search(String),
+    Courses {
+        location: Location,
+        year: Option<u32>,
+    },
+    Raw(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Args {
+    format: Format,
+    config: Option<PathBuf>,
+    command: Command,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Config {
+    autologin: Option<String>,
+    endpoint: Option<String>,
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\nRun 'epitech --help' for usage.", message);
+            process::exit(2);
+        }
+    };
+    if let Err(message) = run(args) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    // An explicitly given configuration file must exist, unlike the default one.
+    let config_path = args
+        .config
+        .or_else(|| {
+            env::var_os("EPITECH_CONFIG")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        })
+        .or_else(|| default_config_path().filter(|path| path.exists()));
+    let config = match config_path {
+        Some(ref path) => {
+            let contents = fs::read_to_string(path)
+                .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+            json::from_str(&contents)
+                .map_err(|err| format!("invalid configuration in {}: {}", path.display(), err))?
+        }
+        None => Config::default(),
+    };
+    let autologin = env::var("EPITECH_AUTOLOGIN")
+        .ok()
+        .or(config.autologin)
+        .ok_or_else(|| {
+            String::from(
+                "no autologin link, set EPITECH_AUTOLOGIN or add it to the configuration file",
+            )
+        })?;
+
+    let mut builder = Client::builder().autologin(autologin);
+    if let Some(endpoint) = env::var("EPITECH_ENDPOINT").ok().or(config.endpoint) {
+        builder = builder.endpoint(endpoint);
+    }
+    let client = builder.authenticate().map_err(describe)?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    execute(&client, args.command, args.format, &mut out).map_err(describe)?;
+    out.flush().map_err(|err| err.to_string())
+}
+
+fn execute(
+    client: &Client,
+    command: Command,
+    format: Format,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let current_year = Local::now().year() as u32;
+    let output = match command {
+        Command::Whoami => {
+            let data = client.fetch_student_data().send()?;
+            let header = &["LOGIN", "NAME", "LOCATION", "PROMO", "SEMESTER"];
+            let rows = vec![vec![
+                data.login.clone(),
+                data.title.clone(),
+                data.location.clone(),
+                optional(data.promo),
+                optional(data.semester),
+            ]];
+            render(&data, header, rows, format)?
+        }
+        Command::Students {
+            location,
+            promo,
+            year,
+            course,
+        } => {
+            let mut builder = client
+                .fetch_student_list()
+                .year(year.unwrap_or(current_year));
+            if let Some(location) = location {
+                builder = builder.location(location);
+            }
+            if let Some(promo) = promo {
+                builder = builder.promo(promo);
+            }
+            if let Some(course) = course {
+                builder = builder.course(course);
+            }
+            return write_students(builder.iter(), format, out);
+        }
+        Command::Notes(login) => {
+            let notes = match login {
+                Some(login) => client.fetch_student_notes(&login)?,
+                None => client.fetch_own_student_notes()?,
+            };
+            let header = &["YEAR", "MODULE", "ACTIVITY", "DATE", "MARK", "GRADER"];
+            let rows = notes
+                .notes
+                .iter()
+                .map(|it| {
+                    vec![
+                        it.scolaryear.to_string(),
+                        it.codemodule.clone(),
+                        it.title.clone(),
+                        it.date.to_string(),
+                        it.final_note.to_string(),
+                        it.correcteur.clone(),
+                    ]
+                })
+                .collect();
+            render(&notes, header, rows, format)?
+        }
+        Command::Netsoul(login) => {
+            let entries = match login {
+                Some(login) => client.fetch_student_netsoul(&login)?,
+                None => client.fetch_own_student_netsoul()?,
+            };
+            let header = &[
+                "DATE",
+                "ACTIVE",
+                "IDLE",
+                "OUT_ACTIVE",
+                "OUT_IDLE",
+                "AVERAGE",
+            ];
+            let rows = entries
+                .iter()
+                .map(|it| {
+                    vec![
+                        it.date.to_string(),
+                        it.active.to_string(),
+                        it.idle.to_string(),
+                        it.out_active.to_string(),
+                        it.out_idle.to_string(),
+                        it.average.to_string(),
+                    ]
+                })
+                .collect();
+            render(&entries, header, rows, format)?
+        }
+        Command::Search(term) => {
+            let results = client.search_student(&term)?;
+            let header = &["LOGIN", "NAME", "TYPE", "COURSE", "PROMO"];
+            let rows = results
+                .iter()
+                .map(|it| {
+                    vec![
+                        it.login.clone(),
+                        optional(it.title.as_ref()),
+                        it.user_type.clone(),
+                        optional(it.course.as_ref()),
+                        optional(it.promo.as_ref()),
+                    ]
+                })
+                .collect();
+            render(&results, header, rows, format)?
+        }
+        Command::Courses { location, year } => {
+            let courses =
+                client.fetch_available_courses(location, year.unwrap_or(current_year), true)?;
+            let header = &["CODE", "TITLE", "STUDENTS"];
+            let rows = courses
+                .iter()
+                .map(|it| vec![it.code.clone(), it.title.clone(), it.students.to_string()])
+                .collect();
+            render(&courses, header, rows, format)?
+        }
+        Command::Raw(path) => {
+            let mut body = client.make_request(path)?;
+            if !body.ends_with('\n') {
+                body.push('\n');
+            }
+            body
+        }
+    };
+    out.write_all(output.as_bytes()).map_err(io_error)
+}
+
+/// Writes the students as soon as they are fetched (except for tables, whose columns are sized after every row).
+fn write_students<I>(students: I, format: Format, out: &mut dyn Write) -> Result<(), Error>
+where
+    I: IntoIterator<Item = Result<UserEntry, Error>>,
+{
+    let header = &["LOGIN", "NAME", "LOCATION"];
+    let row = |it: &UserEntry| vec![it.login.clone(), it.title.clone(), it.location.to_string()];
+    match format {
+        Format::Table => {
+            let rows = students
+                .into_iter()
+                .map(|it| it.map(|it| row(&it)))
+                .collect::<Result<_, _>>()?;
+            let output = render(&(), header, rows, format)?;
+            out.write_all(output.as_bytes()).map_err(io_error)
+        }
+        Format::Csv => {
+            let header: Vec<String> = header.iter().map(|it| it.to_string()).collect();
+            out.write_all(csv_line(&header).as_bytes())
+                .map_err(io_error)?;
+            for student in students {
+                out.write_all(csv_line(&row(&student?)).as_bytes())
+                    .map_err(io_error)?;
+            }
+            Ok(())
+        }
+        // Same output as `json::to_string_pretty` on the whole list.
+        Format::Json => {
+            let mut empty = true;
+            for student in students {
+                let student = json::to_string_pretty(&student?)?.replace('\n', "\n  ");
+                let separator = if empty { "[\n  " } else { ",\n  " };
+                out.write_all(format!("{}{}", separator, student).as_bytes())
+                    .map_err(io_error)?;
+                empty = false;
+            }
+            let end = if empty { "[]\n" } else { "\n]\n" };
+            out.write_all(end.as_bytes()).map_err(io_error)
+        }
+    }
+}
+
+fn io_error(err: io::Error) -> Error {
+    Error::IoError(err.to_string())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|it| it.to_string()).unwrap_or_default()
+}
+
+/// Describes an error along with its causes, hinting at how to fix it when possible.
+fn describe(err: Error) -> String {
+    let mut message = err.to_string();
+    let mut source = StdError::source(&err);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    match err {
+        Error::CookieNotFound | Error::Unauthorized => {
+            format!("{} (is the autologin link still valid?)", message)
+        }
+        _ => message,
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(base) => PathBuf::from(base),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("epitech").join("config.json"))
+}
+
+/// Parses the command-line arguments, returning `None` if the usage message was requested.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Args>, String> {
+    let mut options: HashMap<&'static str, String> = HashMap::new();
+    let mut positionals = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+        let (name, inline) = if let Some(option) = arg.strip_prefix("--") {
+            match option.find('=') {
+                Some(index) => (&option[..index], Some(option[index + 1..].to_string())),
+                None => (option, None),
+            }
+        } else if arg.len() > 1 && arg.starts_with('-') {
+            (&arg[1..], None)
+        } else {
+            positionals.push(arg);
+            continue;
+        };
+        let option = VALUE_OPTIONS
+            .iter()
+            .find(|(long, short)| {
+                *long == name || short.map(|short| name == short.to_string()) == Some(true)
+            })
+            .map(|(long, _)| *long)
+            .ok_or_else(|| format!("unknown option '{}'", arg))?;
+        let value = match inline.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(format!("missing value for '--{}'", option)),
+        };
+        options.insert(option, value);
+    }
+
+    let format_option = options.remove("format");
+    let format = match format_option.as_deref() {
+        None | Some("table") => Format::Table,
+        Some("json") => Format::Json,
+        Some("csv") => Format::Csv,
+        Some(format) => return Err(format!("unknown format '{}'", format)),
+    };
+    let config = options.remove("config").map(PathBuf::from);
+
+    let mut positionals = positionals.into_iter();
+    let name = positionals
+        .next()
+        .ok_or_else(|| String::from("missing command"))?;
+    let (command, allowed): (Command, &[&str]) = match name.as_str() {
+        "whoami" => (Command::Whoami, &[]),
+        "students" => {
+            let command = Command::Students {
+                location: options
+                    .get("location")
+                    .map(|it| parse_location(it))
+                    .transpose()?,
+                promo: options
+                    .get("promo")
+                    .map(|it| it.parse().map_err(|err| format!("{}", err)))
+                    .transpose()?,
+                year: options.get("year").map(|it| parse_year(it)).transpose()?,
+                course: options.get("course").cloned(),
+            };
+            (command, &["location", "promo", "year", "course"])
+        }
+        "notes" => (Command::Notes(positionals.next()), &[]),
+        "netsoul" => (Command::Netsoul(positionals.next()), &[]),
+        "search" => {
+            let term = positionals
+                .next()
+                .ok_or_else(|| String::from("missing search term"))?;
+            (Command::Search(term), &[])
+        }
+        "courses" => {
+            let location = options
+                .get("location")
+                .ok_or_else(|| String::from("missing '--location'"))?;
+            let command = Command::Courses {
+                location: parse_location(location)?,
+                year: options.get("year").map(|it| parse_year(it)).transpose()?,
+            };
+            (command, &["location", "year"])
+        }
+        "raw" => {
+            // The response body is printed as is.
+            if format_option.is_some() {
+                return Err(String::from("'--format' does not apply to 'raw'"));
+            }
+            let path = positionals
+                .next()
+                .ok_or_else(|| String::from("missing path"))?;
+            (Command::Raw(path), &[])
+        }
+        name => return Err(format!("unknown command '{}'", name)),
+    };
+    if let Some(extra) = positionals.next() {
+        return Err(format!("unexpected argument '{}'", extra));
+    }
+    if let Some(option) = options.keys().find(|option| !allowed.contains(option)) {
+        return Err(format!("'--{}' does not apply to '{}'", option, name));
+    }
+
+    Ok(Some(Args {
+        format,
+        config,
+        command,
+    }))
+}
+
+fn parse_location(string: &str) -> Result<Location, String> {
+    string.parse().map_err(|err| format!("{}", err))
+}
+
+fn parse_year(string: &str) -> Result<u32, String> {
+    string
+        .parse()
+        .map_err(|_| format!("invalid year '{}'", string))
+}
+
+/// Renders the rows as a table or as CSV, or `value` as pretty-printed JSON (with every field).
+fn render<T: Serialize>(
+    value: &T,
+    header: &[&str],
+    rows: Vec<Vec<String>>,
+    format: Format,
+) -> Result<String, Error> {
+    let header: Vec<String> = header.iter().map(|it| it.to_string()).collect();
+    let mut output = String::new();
+    match format {
+        Format::Json => {
+            output = json::to_string_pretty(value)?;
+            output.push('\n');
+        }
+        Format::Csv => {
+            for row in std::iter::once(&header).chain(rows.iter()) {
+                output.push_str(&csv_line(row));
+            }
+        }
+        Format::Table => {
+            let mut widths: Vec<usize> = header.iter().map(|it| it.chars().count()).collect();
+            for row in rows.iter() {
+                for (width, field) in widths.iter_mut().zip(row.iter()) {
+                    *width = (*width).max(field.chars().count());
+                }
+            }
+            for row in std::iter::once(&header).chain(rows.iter()) {
+                let fields: Vec<String> = row
+                    .iter()
+                    .zip(widths.iter())
+                    .map(|(field, width)| format!("{:width$}", field, width = width))
+                    .collect();
+                output.push_str(fields.join("  ").trim_end());
+                output.push('\n');
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn csv_line(row: &[String]) -> String {
+    let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+    format!("{}\n", fields.join(","))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn options() {
+        let args = parse(&[
+            "--format=csv",
+            "students",
+            "--location=FR/STG",
+            "--promo",
+            "tek2",
+            "--year=2020",
+        ]);
+        assert_eq!(
+            args,
+            Ok(Some(Args {
+                format: Format::Csv,
+                config: None,
+                command: Command::Students {
+                    location: Some(Location::Strasbourg),
+                    promo: Some(Promo::Tek2),
+                    year: Some(2020),
+                    course: None,
+                },
+            }))
+        );
+
+        let args = parse(&[
+            "-f",
+            "json",
+            "notes",
+            "-c",
+            "config.json",
+            "first.last@epitech.eu",
+        ]);
+        assert_eq!(
+            args,
+            Ok(Some(Args {
+                format: Format::Json,
+                config: Some(PathBuf::from("config.json")),
+                command: Command::Notes(Some(String::from("first.last@epitech.eu"))),
+            }))
+        );
+
+        // Values may start with a dash, and inline values may contain an equal sign.
+        let args = parse(&["raw", "--config", "-", "/user?format=json"]).unwrap();
+        assert_eq!(args.unwrap().config, Some(PathBuf::from("-")));
+        let args = parse(&["--config=a=b.json", "whoami"]).unwrap();
+        assert_eq!(args.unwrap().config, Some(PathBuf::from("a=b.json")));
+
+        assert_eq!(parse(&["whoami", "--help"]), Ok(None));
+        assert_eq!(parse(&["-h"]), Ok(None));
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert_eq!(
+            parse(&["notes", "--location", "FR/STG"]),
+            Err(String::from("'--location' does not apply to 'notes'"))
+        );
+        assert_eq!(
+            parse(&["courses", "--promo=tek1", "--location=FR/PAR"]),
+            Err(String::from("'--promo' does not apply to 'courses'"))
+        );
+        assert_eq!(
+            parse(&["students", "--year"]),
+            Err(String::from("missing value for '--year'"))
+        );
+        assert_eq!(
+            parse(&["whoami", "-f"]),
+            Err(String::from("missing value for '--format'"))
+        );
+        assert_eq!(
+            parse(&["whoami", "--verbose"]),
+            Err(String::from("unknown option '--verbose'"))
+        );
+        assert_eq!(
+            parse(&["whoami", "-x", "1"]),
+            Err(String::from("unknown option '-x'"))
+        );
+        assert_eq!(
+            parse(&["-f", "xml", "whoami"]),
+            Err(String::from("unknown format 'xml'"))
+        );
+        assert_eq!(
+            parse(&["students", "--year=next"]),
+            Err(String::from("invalid year 'next'"))
+        );
+        assert_eq!(
+            parse(&["courses"]),
+            Err(String::from("missing '--location'"))
+        );
+        assert_eq!(
+            parse(&["raw", "/user", "--format", "json"]),
+            Err(String::from("'--format' does not apply to 'raw'"))
+        );
+        assert_eq!(
+            parse(&["whoami", "extra"]),
+            Err(String::from("unexpected argument 'extra'"))
+        );
+        assert_eq!(parse(&[]), Err(String::from("missing command")));
+        assert_eq!(
+            parse(&["grades"]),
+            Err(String::from("unknown command 'grades'"))
+        );
+    }
+
+    #[test]
+    fn streamed_students() {
+        let list: json::Value =
+            json::from_str(include_str!("../../fixtures/user_filter_user.json")).unwrap();
+        let students: Vec<UserEntry> = json::from_value(list["items"].clone()).unwrap();
+        let write = |students: Vec<UserEntry>, format| {
+            let mut out = Vec::new();
+            write_students(students.into_iter().map(Ok), format, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let expected = format!("{}\n", json::to_string_pretty(&students).unwrap());
+        assert_eq!(write(students.clone(), Format::Json), expected);
+        assert_eq!(write(Vec::new(), Format::Json), "[]\n");
+
+        let csv = write(students.clone(), Format::Csv);
+        assert_eq!(csv.lines().count(), students.len() + 1);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("first.last@epitech.eu,Mock Student,FR/STG")
+        );
+
+        // A failing page stops the output with the error.
+        let failing = vec![Ok(students[0].clone()), Err(Error::ServerError(502))];
+        let mut out = Vec::new();
+        let result = write_students(failing, Format::Csv, &mut out);
+        assert_eq!(result, Err(Error::ServerError(502)));
+    }
+
+    #[test]
+    fn error_causes() {
+        let err = Error::RetryLimit(Box::new(Error::ServerError(502)));
+        assert_eq!(
+            describe(err),
+            "no valid response received out of all the allowed retries: \
+             the EPITECH intranet encountered an internal error (502)"
+        );
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(csv_field("first.last@epitech.eu"), "first.last@epitech.eu");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("Doe, John"), "\"Doe, John\"");
+        assert_eq!(csv_field("The \"B\" team"), "\"The \"\"B\"\" team\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("carriage\rreturn"), "\"carriage\rreturn\"");
+
+        let rows = vec![vec![String::from("Doe, John"), String::from("12.5")]];
+        let output = render(&(), &["name", "mark"], rows, Format::Csv).unwrap();
+        assert_eq!(output, "name,mark\n\"Doe, John\",12.5\n");
+    }
+}
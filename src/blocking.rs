@@ -0,0 +1,727 @@
+//! A synchronous facade over `Client`, for programs that are not async (requires the `blocking` feature).
+//!
+//! ```ignore
+//! let client = epitech_api::blocking::Client::builder().autologin(link).authenticate()?;
+//! let notes = client.fetch_own_student_notes()?;
+//! ```
+//!
+//! A blocking client (and all of its clones) runs its requests on a Tokio runtime of its own,
+//! so its methods must not be called from within an async context.
+
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use futures::stream::{Stream, StreamExt};
+use tokio::runtime::{self, Runtime};
+
+use crate::auth::Authenticator;
+use crate::cache::{Cache, CachePolicy};
+use crate::error::Error;
+use crate::response;
+use crate::retry::RetryPolicy;
+use crate::session::Session;
+use crate::transport::{Middleware, Transport};
+use crate::{EventRef, Location, Promo};
+
+/// Builds a blocking `Client`, with the same options as `epitech_api::ClientBuilder`.
+#[derive(Debug, Clone, Default)]
+pub struct ClientBuilder {
+    inner: crate::ClientBuilder,
+}
+
+/// A synchronous version of `epitech_api::Client`.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StudentListFetchBuilder {
+    inner: crate::StudentListFetchBuilder,
+    runtime: Arc<Runtime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StudentDataFetchBuilder {
+    inner: crate::StudentDataFetchBuilder,
+    runtime: Arc<Runtime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlanningFetchBuilder {
+    inner: crate::PlanningFetchBuilder,
+    runtime: Arc<Runtime>,
+}
+
+#[derive(Debug)]
+pub struct BulkFetchBuilder<T> {
+    inner: crate::bulk::BulkFetchBuilder<T>,
+    runtime: Arc<Runtime>,
+}
+
+/// An iterator over the student list, fetching one page at a time (see `StudentListFetchBuilder::iter`).
+pub struct StudentListIter {
+    entries: Pin<Box<dyn Stream<Item = Result<response::UserEntry, Error>>>>,
+    runtime: Arc<Runtime>,
+}
+
+#[derive(Debug)]
+pub struct Download {
+    inner: crate::Download,
+    runtime: Arc<Runtime>,
+}
+
+fn new_runtime() -> Result<Arc<Runtime>, Error> {
+    let runtime = runtime::Builder::new()
+        .threaded_scheduler()
+        .core_threads(1)
+        .thread_name("epitech-api-blocking")
+        .enable_all()
+        .build()
+        .map_err(|err| Error::IoError(err.to_string()))?;
+    Ok(Arc::new(runtime))
+}
+
+#[inline]
+fn block_on<F: Future>(runtime: &Runtime, future: F) -> F::Output {
+    runtime.handle().block_on(future)
+}
+
+impl ClientBuilder {
+    pub fn new() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// See `epitech_api::ClientBuilder::endpoint`.
+    #[inline]
+    pub fn endpoint<T: Into<String>>(self, endpoint: T) -> ClientBuilder {
+        self.inner.endpoint(endpoint).into()
+    }
+
+    #[inline]
+    pub fn autologin<T: Into<String>>(self, autologin: T) -> ClientBuilder {
+        self.inner.autologin(autologin).into()
+    }
+
+    /// See `epitech_api::ClientBuilder::authenticator`.
+    #[inline]
+    pub fn authenticator<A: Authenticator + 'static>(self, authenticator: A) -> ClientBuilder {
+        self.inner.authenticator(authenticator).into()
+    }
+
+    #[inline]
+    pub fn retry_count(self, retry_count: u32) -> ClientBuilder {
+        self.inner.retry_count(retry_count).into()
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> ClientBuilder {
+        self.inner.retry_policy(retry_policy).into()
+    }
+
+    /// See `epitech_api::ClientBuilder::rate_limit`.
+    #[inline]
+    pub fn rate_limit(self, requests: u32, per: Duration) -> ClientBuilder {
+        self.inner.rate_limit(requests, per).into()
+    }
+
+    /// See `epitech_api::ClientBuilder::max_in_flight`.
+    #[inline]
+    pub fn max_in_flight(self, max_in_flight: usize) -> ClientBuilder {
+        self.inner.max_in_flight(max_in_flight).into()
+    }
+
+    #[inline]
+    pub fn cache<C: Cache + 'static>(self, cache: C) -> ClientBuilder {
+        self.inner.cache(cache).into()
+    }
+
+    #[inline]
+    pub fn shared_cache(self, cache: Arc<dyn Cache>) -> ClientBuilder {
+        self.inner.shared_cache(cache).into()
+    }
+
+    #[inline]
+    pub fn cache_policy(self, cache_policy: CachePolicy) -> ClientBuilder {
+        self.inner.cache_policy(cache_policy).into()
+    }
+
+    /// See `epitech_api::ClientBuilder::transport`.
+    #[inline]
+    pub fn transport<T: Transport + 'static>(self, transport: T) -> ClientBuilder {
+        self.inner.transport(transport).into()
+    }
+
+    /// See `epitech_api::ClientBuilder::middleware`.
+    #[inline]
+    pub fn middleware<M: Middleware + 'static>(self, middleware: M) -> ClientBuilder {
+        self.inner.middleware(middleware).into()
+    }
+
+    pub fn authenticate(self) -> Result<Client, Error> {
+        let runtime = new_runtime()?;
+        let inner = block_on(&runtime, self.inner.authenticate())?;
+        Ok(Client { inner, runtime })
+    }
+
+    /// See `epitech_api::ClientBuilder::restore`.
+    pub fn restore(self, session: Session) -> Result<Client, Error> {
+        let runtime = new_runtime()?;
+        let inner = self.inner.restore(session)?;
+        Ok(Client { inner, runtime })
+    }
+}
+
+impl From<crate::ClientBuilder> for ClientBuilder {
+    #[inline]
+    fn from(inner: crate::ClientBuilder) -> ClientBuilder {
+        ClientBuilder { inner }
+    }
+}
+
+impl Client {
+    #[inline]
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// The underlying async client, sharing the session and the cache of this one.
+    #[inline]
+    pub fn as_async(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    #[inline]
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        block_on(&self.runtime, future)
+    }
+
+    #[inline]
+    pub fn endpoint(&self) -> &str {
+        self.inner.endpoint()
+    }
+
+    pub fn make_request<T: ToString>(&self, url: T) -> Result<String, Error> {
+        self.block_on(self.inner.make_request(url))
+    }
+
+    #[inline]
+    pub fn session(&self) -> Session {
+        self.inner.session()
+    }
+
+    pub fn reauthenticate(&self) -> Result<(), Error> {
+        self.block_on(self.inner.reauthenticate())
+    }
+
+    /// See `epitech_api::Client::bypass_cache`.
+    pub fn bypass_cache(&self) -> Client {
+        Client {
+            inner: self.inner.bypass_cache(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// See `epitech_api::Client::make_post_request`.
+    pub fn make_post_request<T: ToString>(
+        &self,
+        url: T,
+        form: &[(&str, &str)],
+    ) -> Result<String, Error> {
+        self.block_on(self.inner.make_post_request(url, form))
+    }
+
+    /// See `epitech_api::Client::download`.
+    pub fn download<T: ToString>(&self, url: T) -> Result<Download, Error> {
+        let inner = self.block_on(self.inner.download(url))?;
+        Ok(Download {
+            inner,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    pub fn fetch_student_list(&self) -> StudentListFetchBuilder {
+        StudentListFetchBuilder {
+            inner: self.inner.fetch_student_list(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_student_data(&self) -> StudentDataFetchBuilder {
+        StudentDataFetchBuilder {
+            inner: self.inner.fetch_student_data(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_planning(&self) -> PlanningFetchBuilder {
+        PlanningFetchBuilder {
+            inner: self.inner.fetch_planning(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_module(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+    ) -> Result<response::ModuleDetail, Error> {
+        self.block_on(self.inner.fetch_module(year, module, instance))
+    }
+
+    pub fn fetch_activity(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<response::ActivityDetail, Error> {
+        self.block_on(self.inner.fetch_activity(year, module, instance, activity))
+    }
+
+    /// See `epitech_api::Client::fetch_autologin`.
+    pub fn fetch_autologin(&self) -> Result<response::AutologinLink, Error> {
+        self.block_on(self.inner.fetch_autologin())
+    }
+
+    /// See `epitech_api::Client::regenerate_autologin`.
+    pub fn regenerate_autologin(&self) -> Result<response::AutologinLink, Error> {
+        self.block_on(self.inner.regenerate_autologin())
+    }
+
+    pub fn register_module(&self, year: u32, module: &str, instance: &str) -> Result<(), Error> {
+        self.block_on(self.inner.register_module(year, module, instance))
+    }
+
+    pub fn unregister_module(&self, year: u32, module: &str, instance: &str) -> Result<(), Error> {
+        self.block_on(self.inner.unregister_module(year, module, instance))
+    }
+
+    pub fn register_event(&self, event: &EventRef) -> Result<(), Error> {
+        self.block_on(self.inner.register_event(event))
+    }
+
+    pub fn unregister_event(&self, event: &EventRef) -> Result<(), Error> {
+        self.block_on(self.inner.unregister_event(event))
+    }
+
+    pub fn fetch_project(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<response::ProjectDetail, Error> {
+        self.block_on(self.inner.fetch_project(year, module, instance, activity))
+    }
+
+    pub fn fetch_project_files(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<Vec<response::ProjectFile>, Error> {
+        self.block_on(
+            self.inner
+                .fetch_project_files(year, module, instance, activity),
+        )
+    }
+
+    pub fn fetch_project_marks(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<Vec<response::ProjectMark>, Error> {
+        self.block_on(
+            self.inner
+                .fetch_project_marks(year, module, instance, activity),
+        )
+    }
+
+    /// See `epitech_api::Client::fetch_mark_detail`.
+    pub fn fetch_mark_detail(
+        &self,
+        mark: &response::UserNotesMark,
+    ) -> Result<response::MarkDetail, Error> {
+        self.block_on(self.inner.fetch_mark_detail(mark))
+    }
+
+    /// See `epitech_api::Client::validate_event_token`.
+    pub fn validate_event_token(
+        &self,
+        event: &EventRef,
+        token: &str,
+    ) -> Result<response::TokenValidation, Error> {
+        self.block_on(self.inner.validate_event_token(event, token))
+    }
+
+    /// See `epitech_api::Client::register_project`.
+    pub fn register_project(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+        members: &[&str],
+    ) -> Result<(), Error> {
+        self.block_on(
+            self.inner
+                .register_project(year, module, instance, activity, members),
+        )
+    }
+
+    pub fn unregister_project(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<(), Error> {
+        self.block_on(
+            self.inner
+                .unregister_project(year, module, instance, activity),
+        )
+    }
+
+    pub fn fetch_many_student_data<I, T>(&self, logins: I) -> BulkFetchBuilder<response::UserData>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        BulkFetchBuilder {
+            inner: self.inner.fetch_many_student_data(logins),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_many_student_netsoul<I, T>(
+        &self,
+        logins: I,
+    ) -> BulkFetchBuilder<Vec<response::UserNetsoulEntry>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        BulkFetchBuilder {
+            inner: self.inner.fetch_many_student_netsoul(logins),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_many_student_notes<I, T>(&self, logins: I) -> BulkFetchBuilder<response::UserNotes>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        BulkFetchBuilder {
+            inner: self.inner.fetch_many_student_notes(logins),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_many_student_binomes<I, T>(
+        &self,
+        logins: I,
+    ) -> BulkFetchBuilder<response::UserBinome>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        BulkFetchBuilder {
+            inner: self.inner.fetch_many_student_binomes(logins),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn fetch_student_netsoul(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::UserNetsoulEntry>, Error> {
+        self.block_on(self.inner.fetch_student_netsoul(login))
+    }
+
+    pub fn fetch_own_student_netsoul(&self) -> Result<Vec<response::UserNetsoulEntry>, Error> {
+        self.block_on(self.inner.fetch_own_student_netsoul())
+    }
+
+    pub fn fetch_student_notes(&self, login: &str) -> Result<response::UserNotes, Error> {
+        self.block_on(self.inner.fetch_student_notes(login))
+    }
+
+    pub fn fetch_own_student_notes(&self) -> Result<response::UserNotes, Error> {
+        self.block_on(self.inner.fetch_own_student_notes())
+    }
+
+    pub fn fetch_student_binomes(&self, login: &str) -> Result<response::UserBinome, Error> {
+        self.block_on(self.inner.fetch_student_binomes(login))
+    }
+
+    pub fn fetch_own_student_binomes(&self) -> Result<response::UserBinome, Error> {
+        self.block_on(self.inner.fetch_own_student_binomes())
+    }
+
+    pub fn fetch_student_notification_messages(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationMessage>, Error> {
+        self.block_on(self.inner.fetch_student_notification_messages(login))
+    }
+
+    pub fn fetch_own_student_notification_messages(
+        &self,
+    ) -> Result<Vec<response::NotificationMessage>, Error> {
+        self.block_on(self.inner.fetch_own_student_notification_messages())
+    }
+
+    pub fn fetch_student_notification_alerts(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationAlert>, Error> {
+        self.block_on(self.inner.fetch_student_notification_alerts(login))
+    }
+
+    pub fn fetch_own_student_notification_alerts(
+        &self,
+    ) -> Result<Vec<response::NotificationAlert>, Error> {
+        self.block_on(self.inner.fetch_own_student_notification_alerts())
+    }
+
+    pub fn fetch_student_notification_missed(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        self.block_on(self.inner.fetch_student_notification_missed(login))
+    }
+
+    pub fn fetch_own_student_notification_missed(
+        &self,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        self.block_on(self.inner.fetch_own_student_notification_missed())
+    }
+
+    pub fn fetch_student_notification_coming(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        self.block_on(self.inner.fetch_student_notification_coming(login))
+    }
+
+    pub fn fetch_own_student_notification_coming(
+        &self,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        self.block_on(self.inner.fetch_own_student_notification_coming())
+    }
+
+    pub fn search_student(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::UserSearchResultEntry>, Error> {
+        self.block_on(self.inner.search_student(login))
+    }
+
+    /// See `epitech_api::Client::fetch_locations`.
+    pub fn fetch_locations(&self) -> Result<Vec<response::LocationEntry>, Error> {
+        self.block_on(self.inner.fetch_locations())
+    }
+
+    pub fn fetch_available_courses(
+        &self,
+        location: Location,
+        year: u32,
+        active: bool,
+    ) -> Result<Vec<response::AvailableCourseEntry>, Error> {
+        self.block_on(self.inner.fetch_available_courses(location, year, active))
+    }
+
+    pub fn fetch_available_promos(
+        &self,
+        location: Location,
+        year: u32,
+        course: &str,
+        active: bool,
+    ) -> Result<Vec<response::AvailablePromoEntry>, Error> {
+        self.block_on(
+            self.inner
+                .fetch_available_promos(location, year, course, active),
+        )
+    }
+}
+
+impl StudentListFetchBuilder {
+    pub fn send(self) -> Result<Vec<response::UserEntry>, Error> {
+        block_on(&self.runtime, self.inner.send())
+    }
+
+    /// See `epitech_api::StudentListFetchBuilder::stream`.
+    pub fn iter(self) -> StudentListIter {
+        StudentListIter {
+            entries: Box::pin(self.inner.stream()),
+            runtime: self.runtime,
+        }
+    }
+
+    #[inline]
+    pub fn location(mut self, location: Location) -> StudentListFetchBuilder {
+        self.inner = self.inner.location(location);
+        self
+    }
+
+    #[inline]
+    pub fn active(mut self, active: bool) -> StudentListFetchBuilder {
+        self.inner = self.inner.active(active);
+        self
+    }
+
+    #[inline]
+    pub fn offset(mut self, offset: u32) -> StudentListFetchBuilder {
+        self.inner = self.inner.offset(offset);
+        self
+    }
+
+    #[inline]
+    pub fn year(mut self, year: u32) -> StudentListFetchBuilder {
+        self.inner = self.inner.year(year);
+        self
+    }
+
+    #[inline]
+    pub fn promo(mut self, promo: Promo) -> StudentListFetchBuilder {
+        self.inner = self.inner.promo(promo);
+        self
+    }
+
+    #[inline]
+    pub fn course<T: Into<String>>(mut self, course: T) -> StudentListFetchBuilder {
+        self.inner = self.inner.course(course);
+        self
+    }
+}
+
+impl Iterator for StudentListIter {
+    type Item = Result<response::UserEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(&self.runtime, self.entries.next())
+    }
+}
+
+impl std::fmt::Debug for StudentListIter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("StudentListIter").finish()
+    }
+}
+
+impl StudentDataFetchBuilder {
+    pub fn send(self) -> Result<response::UserData, Error> {
+        block_on(&self.runtime, self.inner.send())
+    }
+
+    #[inline]
+    pub fn login<T: Into<String>>(mut self, login: T) -> StudentDataFetchBuilder {
+        self.inner = self.inner.login(login);
+        self
+    }
+}
+
+impl PlanningFetchBuilder {
+    pub fn send(self) -> Result<Vec<response::PlanningEvent>, Error> {
+        block_on(&self.runtime, self.inner.send())
+    }
+
+    #[inline]
+    pub fn start(mut self, start: NaiveDate) -> PlanningFetchBuilder {
+        self.inner = self.inner.start(start);
+        self
+    }
+
+    #[inline]
+    pub fn end(mut self, end: NaiveDate) -> PlanningFetchBuilder {
+        self.inner = self.inner.end(end);
+        self
+    }
+
+    #[inline]
+    pub fn semester(mut self, semester: u32) -> PlanningFetchBuilder {
+        self.inner = self.inner.semester(semester);
+        self
+    }
+
+    #[inline]
+    pub fn only_registered(mut self, only_registered: bool) -> PlanningFetchBuilder {
+        self.inner = self.inner.only_registered(only_registered);
+        self
+    }
+
+    #[inline]
+    pub fn only_my_modules(mut self, only_my_modules: bool) -> PlanningFetchBuilder {
+        self.inner = self.inner.only_my_modules(only_my_modules);
+        self
+    }
+}
+
+impl<T> BulkFetchBuilder<T> {
+    /// See `epitech_api::bulk::BulkFetchBuilder::parallelism`.
+    #[inline]
+    pub fn parallelism(mut self, parallelism: usize) -> BulkFetchBuilder<T> {
+        self.inner = self.inner.parallelism(parallelism);
+        self
+    }
+
+    /// See `epitech_api::bulk::BulkFetchBuilder::on_progress`.
+    #[inline]
+    pub fn on_progress<F>(mut self, on_progress: F) -> BulkFetchBuilder<T>
+    where
+        F: FnMut(usize, usize) + Send + 'static,
+    {
+        self.inner = self.inner.on_progress(on_progress);
+        self
+    }
+
+    /// Runs the fetches, returning the result for each login in the same order as they were given.
+    pub fn send(self) -> Vec<(String, Result<T, Error>)> {
+        block_on(&self.runtime, self.inner.send())
+    }
+}
+
+impl Download {
+    /// The MIME type of the file, as announced by the intranet.
+    #[inline]
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner.content_type()
+    }
+
+    /// The size of the file in bytes, if announced by the intranet.
+    #[inline]
+    pub fn content_length(&self) -> Option<u64> {
+        self.inner.content_length()
+    }
+
+    /// Streams the file into `writer`, returning the number of bytes written.
+    pub fn write_to<W: Write>(mut self, writer: &mut W) -> Result<u64, Error> {
+        let mut written = 0;
+        while let Some(chunk) = block_on(&self.runtime, self.inner.response.chunk())? {
+            writer
+                .write_all(&chunk)
+                .map_err(|err| Error::IoError(err.to_string()))?;
+            written += chunk.len() as u64;
+        }
+        writer
+            .flush()
+            .map_err(|err| Error::IoError(err.to_string()))?;
+        Ok(written)
+    }
+
+    /// Reads the whole file in memory.
+    pub fn bytes(self) -> Result<Vec<u8>, Error> {
+        block_on(&self.runtime, self.inner.bytes())
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
+
+use crate::error::Error;
+use crate::Client;
+
+type FetchFn<T> = fn(Client, String) -> BoxFuture<'static, Result<T, Error>>;
+type ProgressFn = Box<dyn FnMut(usize, usize) + Send>;
+
+/// Fetches the same resource for many students, with a bounded number of concurrent requests.
+///
+/// A failure for one student does not abort the whole batch: every login gets its own result.
+pub struct BulkFetchBuilder<T> {
+    client: Client,
+    logins: Vec<String>,
+    parallelism: usize,
+    fetch: FetchFn<T>,
+    on_progress: Option<ProgressFn>,
+}
+
+impl<T> BulkFetchBuilder<T> {
+    pub(crate) fn new(
+        client: Client,
+        logins: Vec<String>,
+        fetch: FetchFn<T>,
+    ) -> BulkFetchBuilder<T> {
+        BulkFetchBuilder {
+            client,
+            logins,
+            parallelism: 8,
+            fetch,
+            on_progress: None,
+        }
+    }
+
+    /// Sets how many requests can be in flight at once (at least one).
+    #[inline]
+    pub fn parallelism(mut self, parallelism: usize) -> BulkFetchBuilder<T> {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Sets a callback that gets invoked with the number of completed fetches and the total number of fetches,
+    /// every time a fetch completes.
+    #[inline]
+    pub fn on_progress<F>(mut self, on_progress: F) -> BulkFetchBuilder<T>
+    where
+        F: FnMut(usize, usize) + Send + 'static,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Runs the fetches, returning the result for each login in the same order as they were given.
+    pub async fn send(self) -> Vec<(String, Result<T, Error>)> {
+        let BulkFetchBuilder {
+            client,
+            logins,
+            parallelism,
+            fetch,
+            mut on_progress,
+        } = self;
+        let total = logins.len();
+        let mut fetches = stream::iter(logins.into_iter().enumerate())
+            .map(|(index, login)| {
+                let future = fetch(client.clone(), login.clone());
+                async move { (index, login, future.await) }
+            })
+            .buffer_unordered(parallelism);
+
+        let mut results = Vec::with_capacity(total);
+        while let Some(result) = fetches.next().await {
+            results.push(result);
+            if let Some(ref mut on_progress) = on_progress {
+                on_progress(results.len(), total);
+            }
+        }
+        results.sort_by_key(|(index, _, _)| *index);
+        results
+            .into_iter()
+            .map(|(_, login, result)| (login, result))
+            .collect()
+    }
+}
+
+impl<T> fmt::Debug for BulkFetchBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BulkFetchBuilder")
+            .field("client", &self.client)
+            .field("logins", &self.logins)
+            .field("parallelism", &self.parallelism)
+            .finish()
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+/// A response body stored in a cache, along with the validators needed to revalidate it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: SystemTime,
+}
+
+impl CacheEntry {
+    /// The time elapsed since the entry was stored (or last revalidated).
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.stored_at)
+            .unwrap_or_default()
+    }
+
+    /// Whether the entry can be revalidated using a conditional request.
+    #[inline]
+    pub fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// A storage backend for intranet responses.
+///
+/// Keys are built out of the authenticated login and the requested URL,
+/// so a single cache can safely be shared by clients of different students.
+pub trait Cache: fmt::Debug + Send + Sync {
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+    fn put(&self, key: &str, entry: CacheEntry);
+    fn remove(&self, key: &str);
+}
+
+/// Decides for how long cached responses are served without contacting the intranet.
+///
+/// Once an entry is older than its TTL, it gets revalidated using `ETag`/`Last-Modified` if possible,
+/// or fetched again otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachePolicy {
+    default_ttl: Duration,
+    rules: Vec<(String, Duration)>,
+}
+
+impl CachePolicy {
+    pub fn new(default_ttl: Duration) -> CachePolicy {
+        CachePolicy {
+            default_ttl,
+            rules: Vec::default(),
+        }
+    }
+
+    /// Sets the TTL of every route starting with `prefix` (eg. `/user/filter/course`).
+    /// When multiple prefixes match a route, the longest one wins.
+    #[inline]
+    pub fn ttl<T: Into<String>>(mut self, prefix: T, ttl: Duration) -> CachePolicy {
+        self.rules.push((prefix.into(), ttl));
+        self
+    }
+
+    /// The TTL applying to the given route, which may also be a full URL (eg. `https://intra.epitech.eu/user`).
+    pub fn ttl_for(&self, route: &str) -> Duration {
+        let path = match route.find("://") {
+            Some(index) => {
+                let rest = &route[index + 3..];
+                rest.find('/').map_or("/", |index| &rest[index..])
+            }
+            None => route,
+        };
+        self.rules
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, ttl)| *ttl)
+            .unwrap_or(self.default_ttl)
+    }
+}
+
+impl Default for CachePolicy {
+    #[inline]
+    fn default() -> CachePolicy {
+        CachePolicy::new(Duration::from_secs(60))
+    }
+}
+
+/// An in-memory cache, evicting the least recently used entries once its capacity is reached.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    state: Mutex<MemoryCacheState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryCacheState {
+    clock: u64,
+    entries: HashMap<String, (u64, CacheEntry)>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> MemoryCache {
+        MemoryCache {
+            capacity: capacity.max(1),
+            state: Mutex::new(MemoryCacheState::default()),
+        }
+    }
+}
+
+impl Cache for MemoryCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let (last_used, entry) = state.entries.get_mut(key)?;
+        *last_used = clock;
+        Some(entry.clone())
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        state.entries.insert(key.to_string(), (clock, entry));
+        while state.entries.len() > self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => state.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        self.state.lock().unwrap().entries.remove(key);
+    }
+}
+
+/// An on-disk cache, storing each entry as a JSON file in a directory.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    directory: PathBuf,
+}
+
+impl DiskCache {
+    /// Creates a cache stored in `directory`, which gets created if needed.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> std::io::Result<DiskCache> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(DiskCache { directory })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        // FNV-1a, to get file names that are stable across runs and platforms.
+        let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        self.directory.join(format!("{:016x}.json", hash))
+    }
+}
+
+impl Cache for DiskCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let contents = fs::read_to_string(self.path(key)).ok()?;
+        let (stored_key, entry) = json::from_str::<(String, CacheEntry)>(&contents).ok()?;
+        if stored_key == key {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        if let Ok(contents) = json::to_string(&(key, entry)) {
+            let _ = fs::write(self.path(key), contents);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+}
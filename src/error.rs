@@ -3,9 +3,17 @@ use thiserror::Error;
 #[derive(Error, Debug, Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub enum Error {
     #[error("no valid response received out of all the allowed retries")]
-    RetryLimit,
+    RetryLimit(#[source] Box<Error>),
     #[error("invalid status code ({0})")]
     InvalidStatusCode(u16),
+    #[error("the session is not authorized to access this resource")]
+    Unauthorized,
+    #[error("access to this resource is forbidden")]
+    Forbidden,
+    #[error("resource not found: '{0}'")]
+    NotFound(String),
+    #[error("the EPITECH intranet encountered an internal error ({0})")]
+    ServerError(u16),
     #[error("the session cookie couldn't be extracted")]
     CookieNotFound,
     #[error("the EPITECH intranet couldn't be reached")]
@@ -16,6 +24,77 @@ pub enum Error {
     ParserError(String),
     #[error("request error: '{0}'")]
     RequestError(String),
+    #[error("invalid request: '{0}'")]
+    InvalidRequest(String),
+    #[error("I/O error: '{0}'")]
+    IoError(String),
+    #[error("the registration is closed")]
+    RegistrationClosed,
+    #[error("there are no seats left")]
+    RegistrationFull,
+    #[error("not enough credits to register")]
+    MissingCredits,
+    #[error("the intranet refused the request ({0}): '{1}'")]
+    RequestRefused(u16, String),
+    #[error("no recorded interaction matches the request: '{0}'")]
+    UnrecordedRequest(String),
+}
+
+impl Error {
+    /// Maps an unsuccessful status code to the corresponding error.
+    pub(crate) fn from_status(status: u16, path: &str) -> Error {
+        match status {
+            401 => Error::Unauthorized,
+            403 => Error::Forbidden,
+            404 => Error::NotFound(path.to_string()),
+            500..=599 => Error::ServerError(status),
+            _ => Error::InvalidStatusCode(status),
+        }
+    }
+
+    /// Whether the error is likely to go away by retrying the same request.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::UnreachableRemote | Error::RequestError(_) | Error::ServerError(_) => true,
+            Error::InvalidStatusCode(status) => *status == 408 || *status == 429,
+            _ => false,
+        }
+    }
+
+    /// Maps an error reply of the intranet to the most specific error variant.
+    ///
+    /// Replies without a known message are mapped like `from_status` for authorization,
+    /// missing resource and server errors, so that GET and POST requests fail alike.
+    pub(crate) fn from_intranet_failure(status: u16, path: &str, body: &str) -> Error {
+        let message = intranet_message(body);
+        let lowered = message.to_lowercase();
+        let matches = |phrases: &[&str]| {
+            phrases
+                .iter()
+                .any(|phrase| contains_phrase(&lowered, phrase))
+        };
+        if matches(&[
+            "closed", "expired", "fermé", "fermée", "fermées", "clos", "closes", "terminé",
+        ]) {
+            Error::RegistrationClosed
+        } else if matches(&[
+            "full",
+            "no more seats",
+            "no seats left",
+            "complet",
+            "complète",
+            "plus de place",
+            "plus de places",
+        ]) {
+            Error::RegistrationFull
+        } else if matches(&["credit", "credits", "crédit", "crédits"]) {
+            Error::MissingCredits
+        } else if matches!(status, 401 | 404 | 500..=599) {
+            Error::from_status(status, path)
+        } else {
+            Error::RequestRefused(status, message)
+        }
+    }
 }
 
 impl From<json::Error> for Error {
@@ -29,3 +108,41 @@ impl From<reqwest::Error> for Error {
         Error::RequestError(v.to_string())
     }
 }
+
+/// Extracts the message out of an error reply of the intranet (eg. `{"error":"..."}`).
+pub(crate) fn intranet_message(body: &str) -> String {
+    json::from_str::<json::Value>(body)
+        .ok()
+        .and_then(|value| {
+            let message = value.get("error").or_else(|| value.get("message"))?;
+            message.as_str().map(String::from)
+        })
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Whether a reply of the intranet carries an error message (eg. `{"error":"..."}`),
+/// which it sometimes sends along with a successful status code.
+pub(crate) fn is_error_reply(body: &str) -> bool {
+    json::from_str::<json::Value>(body)
+        .ok()
+        .is_some_and(|value| value.get("error").is_some())
+}
+
+/// Whether `message` contains `phrase` as whole words (eg. `over` is not found in `overlap`).
+pub(crate) fn contains_phrase(message: &str, phrase: &str) -> bool {
+    message.match_indices(phrase).any(|(index, _)| {
+        let before = message[..index].chars().next_back();
+        let after = message[index + phrase.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// The error returned when parsing a malformed location code (eg. `FR/PAR`).
+#[derive(Error, Debug, Clone, Eq, Ord, PartialEq, PartialOrd)]
+#[error("invalid location code: '{0}'")]
+pub struct ParseLocationError(pub String);
+
+/// The error returned when parsing a malformed promo code (eg. `tek1`).
+#[derive(Error, Debug, Clone, Eq, Ord, PartialEq, PartialOrd)]
+#[error("invalid promo code: '{0}'")]
+pub struct ParsePromoError(pub String);
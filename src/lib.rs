@@ -2,116 +2,132 @@ use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
 
 use chrono::prelude::*;
-use enum_iterator::IntoEnumIterator;
+use futures::future::FutureExt;
+use futures::stream::{self, Stream, TryStreamExt};
 use reqwest::header;
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::OwnedSemaphorePermit;
 
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod bulk;
+pub mod cache;
 pub mod error;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod netsoul;
 pub mod response;
+pub mod retry;
+pub mod session;
+pub mod transport;
+pub mod vcr;
+
+mod limit;
+mod serde_utils;
 
 #[cfg(test)]
 mod tests;
 
-use crate::error::Error;
+use crate::auth::{Authenticator, AutologinAuthenticator};
+use crate::bulk::BulkFetchBuilder;
+use crate::cache::{Cache, CacheEntry, CachePolicy};
+use crate::error::{Error, ParseLocationError, ParsePromoError};
+use crate::limit::Limiter;
+use crate::retry::RetryPolicy;
+use crate::session::Session;
+use crate::transport::{Layered, Middleware, ReqwestTransport, Transport};
 
 pub static ENDPOINT: &str = "https://intra.epitech.eu";
 
 #[derive(Debug, Clone, Default)]
 pub struct ClientBuilder {
+    endpoint: Option<String>,
     autologin: String,
-    retry_count: u32,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    retry_policy: RetryPolicy,
+    rate_limit: Option<(u32, Duration)>,
+    max_in_flight: Option<usize>,
+    cache: Option<Arc<dyn Cache>>,
+    cache_policy: CachePolicy,
+    transport: Option<Arc<dyn Transport>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Client {
-    autologin: String,
-    retry_count: u32,
-    client: reqwest::Client,
-    login: String,
-}
-
-#[derive(
-    Debug,
-    Clone,
-    Copy,
-    PartialEq,
-    PartialOrd,
-    Eq,
-    Ord,
-    Hash,
-    Serialize,
-    Deserialize,
-    IntoEnumIterator,
-)]
+    endpoint: String,
+    session: Arc<RwLock<Session>>,
+    /// Shared by all the clones, so that `regenerate_autologin` can replace a revoked autologin link.
+    authenticator: Arc<RwLock<Option<Arc<dyn Authenticator>>>>,
+    retry_policy: RetryPolicy,
+    limiter: Limiter,
+    cache: Option<Arc<dyn Cache>>,
+    cache_policy: CachePolicy,
+    bypass_cache: bool,
+    transport: Arc<dyn Transport>,
+    /// Held while re-authenticating, so that concurrent requests do not re-authenticate more than once.
+    renewal: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// A campus, as identified by its code on the intranet (eg. `FR/PAR`).
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum Location {
-    #[serde(rename = "ES/BAR")]
     Barcelone,
-    #[serde(rename = "DE/BER")]
     Berlin,
-    #[serde(rename = "FR/BDX")]
     Bordeaux,
-    #[serde(rename = "FR/RUN")]
     LaReunion,
-    #[serde(rename = "FR/LIL")]
     Lille,
-    #[serde(rename = "FR/LYN")]
     Lyon,
-    #[serde(rename = "FR/MAR")]
     Marseille,
-    #[serde(rename = "FR/MPL")]
     Montpellier,
-    #[serde(rename = "FR/NCY")]
     Nancy,
-    #[serde(rename = "FR/NAN")]
     Nantes,
-    #[serde(rename = "FR/NCE")]
     Nice,
-    #[serde(rename = "FR/PAR")]
     Paris,
-    #[serde(rename = "FR/REN")]
     Rennes,
-    #[serde(rename = "FR/STG")]
     Strasbourg,
-    #[serde(rename = "FR/TLS")]
     Toulouse,
-    #[serde(rename = "BJ/COT")]
     Cotonou,
-    #[serde(rename = "AL/TIR")]
     Tirana,
-    #[serde(rename = "BE/BRU")]
     Bruxelles,
+    /// A campus unknown to this library, holding its code.
+    Other(String),
 }
 
-#[derive(
-    Debug,
-    Clone,
-    Copy,
-    PartialEq,
-    PartialOrd,
-    Eq,
-    Ord,
-    Hash,
-    Serialize,
-    Deserialize,
-    IntoEnumIterator,
-)]
+/// A promotion, as identified by its code on the intranet (eg. `tek1`).
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum Promo {
-    #[serde(rename = "tek1")]
     Tek1,
-    #[serde(rename = "tek2")]
     Tek2,
-    #[serde(rename = "tek3")]
     Tek3,
-    #[serde(rename = "wac1")]
     Wac1,
-    #[serde(rename = "wac2")]
     Wac2,
-    #[serde(rename = "msc3")]
     Msc3,
-    #[serde(rename = "msc4")]
     Msc4,
+    /// A promotion unknown to this library, holding its code.
+    Other(String),
+}
+
+#[derive(Debug)]
+pub struct Download {
+    response: transport::StreamingResponse,
+    /// Counts the download as in flight until it gets dropped.
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EventRef {
+    pub scolaryear: u32,
+    pub codemodule: String,
+    pub codeinstance: String,
+    pub codeacti: String,
+    pub codeevent: String,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -131,6 +147,27 @@ pub struct StudentDataFetchBuilder {
     login: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct PlanningFetchBuilder {
+    client: Client,
+    start: NaiveDate,
+    end: NaiveDate,
+    semesters: Vec<u32>,
+    only_registered: bool,
+    only_my_modules: bool,
+}
+
+enum Fetched {
+    Body(CacheEntry),
+    NotModified,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NotificationEvents {
+    #[serde(alias = "recents", alias = "coming")]
+    pub events: Vec<response::NotificationEvent>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct UserEntries {
     pub total: usize,
@@ -140,70 +177,179 @@ struct UserEntries {
 impl ClientBuilder {
     pub fn new() -> ClientBuilder {
         ClientBuilder {
+            endpoint: None,
             autologin: String::default(),
-            retry_count: 5,
+            authenticator: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limit: None,
+            max_in_flight: None,
+            cache: None,
+            cache_policy: CachePolicy::default(),
+            transport: None,
+            middlewares: Vec::default(),
         }
     }
 
+    /// Sets the base URL of the intranet (defaults to `ENDPOINT`), eg. to target a mock server.
+    #[inline]
+    pub fn endpoint<T: Into<String>>(mut self, endpoint: T) -> ClientBuilder {
+        self.endpoint = Some(endpoint.into().trim_end_matches('/').to_string());
+        self
+    }
+
     #[inline]
     pub fn autologin<T: Into<String>>(mut self, autologin: T) -> ClientBuilder {
         self.autologin = autologin.into();
         self
     }
 
+    /// Authenticates using `authenticator` instead of an autologin link.
+    ///
+    /// The authenticator is kept by the client to re-authenticate once the session cookie expires.
+    #[inline]
+    pub fn authenticator<A: Authenticator + 'static>(mut self, authenticator: A) -> ClientBuilder {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Sets the maximum number of times a request is sent, keeping the rest of the retry policy.
     #[inline]
     pub fn retry_count(mut self, retry_count: u32) -> ClientBuilder {
-        self.retry_count = retry_count;
+        self.retry_policy = self.retry_policy.max_attempts(retry_count);
         self
     }
 
-    pub async fn authenticate(self) -> Result<Client, Error> {
-        let client = match reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .build()
-        {
-            Ok(x) => x,
-            Err(_) => return Err(Error::InternalError),
+    #[inline]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ClientBuilder {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Limits the client (and all of its clones) to sending at most `requests` requests per `per` duration,
+    /// allowing bursts of up to `requests` requests.
+    #[inline]
+    pub fn rate_limit(mut self, requests: u32, per: Duration) -> ClientBuilder {
+        self.rate_limit = Some((requests, per));
+        self
+    }
+
+    /// Limits the number of requests simultaneously in flight for the client and all of its clones.
+    #[inline]
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> ClientBuilder {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Caches the responses of `Client::make_request` (and every fetch built on top of it) in `cache`.
+    #[inline]
+    pub fn cache<C: Cache + 'static>(mut self, cache: C) -> ClientBuilder {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Same as `cache`, for a cache shared with other clients.
+    #[inline]
+    pub fn shared_cache(mut self, cache: Arc<dyn Cache>) -> ClientBuilder {
+        self.cache = Some(cache);
+        self
+    }
+
+    #[inline]
+    pub fn cache_policy(mut self, cache_policy: CachePolicy) -> ClientBuilder {
+        self.cache_policy = cache_policy;
+        self
+    }
+
+    /// Sends the client's requests through `transport` (eg. a `vcr::Recorder`) instead of `ReqwestTransport`.
+    #[inline]
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> ClientBuilder {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Runs `middleware` in front of the transport, for every request of the client and its authenticator.
+    ///
+    /// Middlewares run in the order they were added: the first one sees the requests first.
+    #[inline]
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> ClientBuilder {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    pub async fn authenticate(mut self) -> Result<Client, Error> {
+        let authenticator = match self.authenticator {
+            Some(ref authenticator) => authenticator.clone(),
+            None => Arc::new(AutologinAuthenticator::new(self.autologin.clone())),
         };
-        match client.get(&self.autologin).send().await {
-            Ok(response) => {
-                let headers = response.headers();
-                let cookie = headers
-                    .get_all(header::SET_COOKIE)
-                    .iter()
-                    .filter_map(|it| it.to_str().ok())
-                    .find(|cookie| cookie.starts_with("user="))
-                    .and_then(|cookie| cookie.split(';').nth(0))
-                    .and_then(|cookie| header::HeaderValue::from_str(cookie).ok())
-                    .ok_or(Error::CookieNotFound)?;
-
-                let mut headers = header::HeaderMap::new();
-                headers.insert(header::COOKIE, cookie);
-                let autologin = self.autologin;
-                let retry_count = self.retry_count;
-                let client = reqwest::Client::builder()
-                    .default_headers(headers)
-                    .build()
-                    .map_err(|_| Error::InternalError)?;
-                let login = String::default();
-                let mut client = Client {
-                    autologin,
-                    retry_count,
-                    client,
-                    login,
-                };
-                let data = client.fetch_student_data().send().await?;
-                client.login = data.login.clone();
-                Ok(client)
-            }
-            Err(err) => {
-                let status = err.status();
-                match status {
-                    Some(status) => Err(Error::InvalidStatusCode(status.as_u16())),
-                    None => Err(Error::UnreachableRemote),
-                }
+        let transport = self.transport_stack()?;
+        let endpoint = self.endpoint.as_deref().unwrap_or(ENDPOINT);
+        let credentials = authenticator
+            .authenticate(transport.as_ref(), endpoint)
+            .await?;
+        let session = Session {
+            login: String::default(),
+            cookie: credentials.cookie,
+            autologin: authenticator.autologin().map(String::from),
+            expires: credentials.expires,
+        };
+        self.authenticator = Some(authenticator);
+        let mut client = self.build(session)?;
+        // The cache is only plugged in once the login is known, as it is part of the cache keys.
+        let cache = client.cache.take();
+        let data = client.fetch_student_data().send().await?;
+        client.session.write().unwrap().login = data.login;
+        client.cache = cache;
+        Ok(client)
+    }
+
+    /// Rebuilds a client out of a previously saved `Session`, without contacting the intranet.
+    ///
+    /// Once the session cookie expires, the client re-authenticates using the builder's authenticator if any,
+    /// or else the autologin link of the session (or, if it holds none, the one given to the builder).
+    pub fn restore(mut self, mut session: Session) -> Result<Client, Error> {
+        if session.autologin.is_none() && !self.autologin.is_empty() {
+            session.autologin = Some(self.autologin.clone());
+        }
+        if self.authenticator.is_none() {
+            if let Some(ref autologin) = session.autologin {
+                let authenticator = AutologinAuthenticator::new(autologin.clone());
+                self.authenticator = Some(Arc::new(authenticator));
             }
         }
+        self.build(session)
+    }
+
+    /// Wraps the transport into the middlewares, which are then consumed.
+    fn transport_stack(&mut self) -> Result<Arc<dyn Transport>, Error> {
+        let transport: Arc<dyn Transport> = match self.transport.take() {
+            Some(transport) => transport,
+            None => Arc::new(ReqwestTransport::new()?),
+        };
+        let transport = self
+            .middlewares
+            .drain(..)
+            .rev()
+            .fold(transport, |inner, middleware| {
+                Arc::new(Layered::new(middleware, inner))
+            });
+        self.transport = Some(transport.clone());
+        Ok(transport)
+    }
+
+    fn build(mut self, session: Session) -> Result<Client, Error> {
+        let transport = self.transport_stack()?;
+        Ok(Client {
+            endpoint: self.endpoint.unwrap_or_else(|| String::from(ENDPOINT)),
+            authenticator: Arc::new(RwLock::new(self.authenticator)),
+            retry_policy: self.retry_policy,
+            limiter: Limiter::new(self.rate_limit, self.max_in_flight),
+            cache: self.cache,
+            cache_policy: self.cache_policy,
+            bypass_cache: false,
+            transport,
+            renewal: Arc::default(),
+            session: Arc::new(RwLock::new(session)),
+        })
     }
 }
 
@@ -213,27 +359,350 @@ impl Client {
         ClientBuilder::new()
     }
 
-    pub async fn make_request<T: ToString>(&self, url: T) -> Result<String, Error> {
+    /// The base URL of the intranet this client talks to.
+    #[inline]
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_str()
+    }
+
+    fn build_url<T: ToString>(&self, url: T) -> String {
         let mut string = url.to_string();
         if !string.contains("&format=json") && !string.contains("?format=json") {
             let b = string.contains('?');
             string.push(if b { '&' } else { '?' });
             string.push_str("format=json");
         }
-        if !string.starts_with(ENDPOINT) {
-            string.insert_str(0, ENDPOINT);
+        self.absolute_url(string)
+    }
+
+    /// Prefixes a path with the client's endpoint, leaving absolute URLs untouched
+    /// (see `request` for how they are authenticated).
+    fn absolute_url(&self, url: String) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            url
+        } else {
+            format!("{}{}", self.endpoint, url)
+        }
+    }
+
+    /// Sends a GET request to the intranet and returns the response body.
+    ///
+    /// Failed requests are retried according to the client's `RetryPolicy`
+    /// (by default, only transient failures such as server errors are retried).
+    ///
+    /// If the client has a cache, fresh responses are served from it,
+    /// and stale ones are revalidated using a conditional request when possible.
+    ///
+    /// If the session cookie is missing or has expired (or if the intranet answers `401 Unauthorized`),
+    /// the client re-authenticates using its authenticator and sends the request again.
+    ///
+    /// Absolute URLs are accepted too, but only the ones pointing at the client's endpoint carry the session cookie.
+    pub async fn make_request<T: ToString>(&self, url: T) -> Result<String, Error> {
+        let path = url.to_string();
+        let cookie = self.ensure_session().await?;
+        match self.fetch(&path).await {
+            Err(Error::Unauthorized)
+                if self.can_reauthenticate()
+                    && self.is_intranet_url(&self.absolute_url(path.clone())) =>
+            {
+                self.renew_session(&cookie).await?;
+                self.fetch(&path).await
+            }
+            result => result,
+        }
+    }
+
+    /// The login of the authenticated student.
+    fn login(&self) -> String {
+        self.session.read().unwrap().login.clone()
+    }
+
+    /// The current session of the client, which can be saved and later passed to `ClientBuilder::restore`.
+    pub fn session(&self) -> Session {
+        self.session.read().unwrap().clone()
+    }
+
+    /// Obtains a fresh session cookie using the client's authenticator,
+    /// which gets shared by all the clones of this client.
+    pub async fn reauthenticate(&self) -> Result<(), Error> {
+        let _renewal = self.renewal.lock().await;
+        self.renew().await
+    }
+
+    async fn renew(&self) -> Result<(), Error> {
+        let authenticator = self.authenticator.read().unwrap().clone();
+        let authenticator = authenticator.ok_or(Error::Unauthorized)?;
+        let credentials = authenticator
+            .authenticate(self.transport.as_ref(), &self.endpoint)
+            .await?;
+        let mut session = self.session.write().unwrap();
+        session.cookie = credentials.cookie;
+        session.expires = credentials.expires;
+        Ok(())
+    }
+
+    fn can_reauthenticate(&self) -> bool {
+        let authenticator = self.authenticator.read().unwrap();
+        authenticator
+            .as_ref()
+            .is_some_and(|authenticator| authenticator.renewable())
+    }
+
+    /// Re-authenticates ahead of time if the session cookie is missing or known to be expired,
+    /// and returns the cookie requests are about to be sent with.
+    async fn ensure_session(&self) -> Result<String, Error> {
+        let (cookie, stale) = {
+            let session = self.session.read().unwrap();
+            let stale = session.cookie.is_empty() || session.is_expired();
+            (session.cookie.clone(), stale)
+        };
+        if stale && self.can_reauthenticate() {
+            return self.renew_session(&cookie).await;
+        }
+        Ok(cookie)
+    }
+
+    /// Re-authenticates, unless a concurrent request already replaced `stale_cookie`,
+    /// and returns the fresh cookie.
+    async fn renew_session(&self, stale_cookie: &str) -> Result<String, Error> {
+        let _renewal = self.renewal.lock().await;
+        if self.session.read().unwrap().cookie == stale_cookie {
+            self.renew().await?;
+        }
+        Ok(self.session.read().unwrap().cookie.clone())
+    }
+
+    /// Prepares a request, carrying the session cookie if it targets the client's endpoint.
+    ///
+    /// Absolute URLs pointing elsewhere (eg. profile pictures served by a CDN) are sent without it,
+    /// so that the session does not leak to other hosts.
+    fn request(&self, method: &str, url: &str) -> Result<transport::Request, Error> {
+        let request = transport::Request::new(method, url);
+        if !self.is_intranet_url(url) {
+            return Ok(request);
         }
-        for _ in 0..self.retry_count {
-            let result = self.client.get(&string).send().await;
-            let result = match result {
-                Ok(val) => val.text().await,
-                Err(err) => Err(err),
+        let cookie = self.session.read().unwrap().cookie_header()?;
+        let cookie = cookie.to_str().map_err(|_| Error::CookieNotFound)?;
+        Ok(request.header(header::COOKIE.as_str(), cookie))
+    }
+
+    /// Whether `url` has the same origin (scheme, host and port) as the client's endpoint.
+    fn is_intranet_url(&self, url: &str) -> bool {
+        match (
+            reqwest::Url::parse(url),
+            reqwest::Url::parse(&self.endpoint),
+        ) {
+            (Ok(url), Ok(endpoint)) => url.origin() == endpoint.origin(),
+            _ => false,
+        }
+    }
+
+    async fn fetch(&self, path: &str) -> Result<String, Error> {
+        let string = self.build_url(path);
+        let key = self.cache_key(&string);
+        let cached = match self.cache {
+            Some(ref cache) if !self.bypass_cache => cache.get(&key),
+            _ => None,
+        };
+        if let Some(ref entry) = cached {
+            if entry.age() < self.cache_policy.ttl_for(path) {
+                return Ok(entry.body.clone());
+            }
+        }
+        let attempts = self.retry_policy.attempts();
+        let mut attempt = 0;
+        loop {
+            let (err, retry_after) = match self.send_get(&string, path, cached.as_ref()).await {
+                Ok(Fetched::Body(entry)) => {
+                    let body = entry.body.clone();
+                    if let Some(ref cache) = self.cache {
+                        cache.put(&key, entry);
+                    }
+                    return Ok(body);
+                }
+                Ok(Fetched::NotModified) => match cached {
+                    Some(mut entry) => {
+                        entry.stored_at = SystemTime::now();
+                        let body = entry.body.clone();
+                        if let Some(ref cache) = self.cache {
+                            cache.put(&key, entry);
+                        }
+                        return Ok(body);
+                    }
+                    None => (Error::InvalidStatusCode(304), None),
+                },
+                Err(failure) => failure,
             };
-            if let Ok(body) = result {
-                return Ok(body);
+            if !self.retry_policy.should_retry(&err) {
+                return Err(err);
+            }
+            attempt += 1;
+            if attempt >= attempts {
+                // Without any retry allowed, the failure is returned as is.
+                return Err(match attempts {
+                    1 => err,
+                    _ => Error::RetryLimit(Box::new(err)),
+                });
+            }
+            let delay = self.retry_policy.delay(attempt - 1, retry_after);
+            tokio::time::delay_for(delay).await;
+        }
+    }
+
+    fn cache_key(&self, url: &str) -> String {
+        format!("{}|{}", self.login(), url)
+    }
+
+    /// Evicts the cached responses of the resources a successful POST request to `path` may have changed,
+    /// ie. every parent of its route (eg. the module and the activity of an event registration).
+    fn invalidate(&self, path: &str) {
+        let cache = match self.cache {
+            Some(ref cache) => cache,
+            None => return,
+        };
+        let mut parent = path.split('?').next().unwrap_or_default();
+        while let Some(index) = parent.trim_end_matches('/').rfind('/') {
+            parent = &parent[..index];
+            if parent.is_empty() || parent.ends_with('/') {
+                break;
+            }
+            // Routes are requested both with and without a trailing slash.
+            for route in [parent.to_string(), format!("{}/", parent)].iter() {
+                cache.remove(&self.cache_key(&self.build_url(route)));
+            }
+        }
+    }
+
+    /// Sends a single GET request, returning the delay requested through `Retry-After` on failure.
+    ///
+    /// If `cached` holds validators, the request is made conditional.
+    async fn send_get(
+        &self,
+        url: &str,
+        path: &str,
+        cached: Option<&CacheEntry>,
+    ) -> Result<Fetched, (Error, Option<Duration>)> {
+        let mut request = self.request("GET", url).map_err(|err| (err, None))?;
+        if let Some(entry) = cached {
+            if let Some(ref etag) = entry.etag {
+                request = request.header(header::IF_NONE_MATCH.as_str(), etag.as_str());
+            }
+            if let Some(ref last_modified) = entry.last_modified {
+                request =
+                    request.header(header::IF_MODIFIED_SINCE.as_str(), last_modified.as_str());
             }
         }
-        Err(Error::RetryLimit)
+        let _permit = self.limiter.acquire().await;
+        let response = self
+            .transport
+            .send(request)
+            .await
+            .map_err(|err| (err, None))?;
+        if response.status == reqwest::StatusCode::NOT_MODIFIED.as_u16() {
+            return Ok(Fetched::NotModified);
+        }
+        if !response.is_success() {
+            let retry_after = response
+                .header_value(header::RETRY_AFTER.as_str())
+                .and_then(retry::parse_retry_after);
+            return Err((Error::from_status(response.status, path), retry_after));
+        }
+        let header_value =
+            |name: header::HeaderName| response.header_value(name.as_str()).map(String::from);
+        Ok(Fetched::Body(CacheEntry {
+            etag: header_value(header::ETAG),
+            last_modified: header_value(header::LAST_MODIFIED),
+            body: response.text(),
+            stored_at: SystemTime::now(),
+        }))
+    }
+
+    /// Returns a clone of the client whose requests skip the cache lookup
+    /// (the fresh responses still get stored in the cache).
+    pub fn bypass_cache(&self) -> Client {
+        let mut client = self.clone();
+        client.bypass_cache = true;
+        client
+    }
+
+    /// Sends a form-encoded POST request to the intranet.
+    ///
+    /// Unlike `make_request`, this is never retried, as the intranet's POST routes are not idempotent.
+    /// Once it succeeds, the cached responses of the parents of the route (eg. the module of a registration) are evicted.
+    /// The intranet reports failures using an error message, sometimes along with a successful status code,
+    /// which gets mapped to the corresponding `Error` variant.
+    pub async fn make_post_request<T: ToString>(
+        &self,
+        url: T,
+        form: &[(&str, &str)],
+    ) -> Result<String, Error> {
+        let path = url.to_string();
+        let (status, body) = self.send_post(&path, form).await?;
+        if (200..300).contains(&status) && !error::is_error_reply(&body) {
+            self.invalidate(&path);
+            Ok(body)
+        } else {
+            Err(Error::from_intranet_failure(status, &path, &body))
+        }
+    }
+
+    async fn send_post<T: ToString>(
+        &self,
+        url: T,
+        form: &[(&str, &str)],
+    ) -> Result<(u16, String), Error> {
+        let string = self.build_url(url);
+        let cookie = self.ensure_session().await?;
+        let response = self.post(&string, form).await?;
+        if response.status == 401 && self.can_reauthenticate() {
+            self.renew_session(&cookie).await?;
+            let response = self.post(&string, form).await?;
+            return Ok((response.status, response.text()));
+        }
+        Ok((response.status, response.text()))
+    }
+
+    async fn post(&self, url: &str, form: &[(&str, &str)]) -> Result<transport::Response, Error> {
+        let request = self.request("POST", url)?.form(form);
+        let _permit = self.limiter.acquire().await;
+        self.transport.send(request).await
+    }
+
+    /// Starts downloading a file from the intranet (eg. a project subject or a profile picture).
+    ///
+    /// Unlike `make_request`, no `format=json` parameter is appended to the URL.
+    /// The request goes through the transport and its middlewares (see `Middleware::handle_streaming`),
+    /// and the body is not read until `Download::write_to` or `Download::bytes` gets called,
+    /// unless the transport buffers it (eg. `FnTransport`).
+    ///
+    /// Files hosted outside of the client's endpoint (eg. on a CDN) are downloaded without the session cookie.
+    pub async fn download<T: ToString>(&self, url: T) -> Result<Download, Error> {
+        let path = url.to_string();
+        let string = self.absolute_url(path.clone());
+        let cookie = self.ensure_session().await?;
+        match self.send_download(&string, &path).await {
+            Err(Error::Unauthorized)
+                if self.can_reauthenticate() && self.is_intranet_url(&string) =>
+            {
+                self.renew_session(&cookie).await?;
+                self.send_download(&string, &path).await
+            }
+            result => result,
+        }
+    }
+
+    async fn send_download(&self, url: &str, path: &str) -> Result<Download, Error> {
+        let request = self.request("GET", url)?;
+        let permit = self.limiter.acquire().await;
+        let response = self.transport.send_streaming(request).await?;
+        if response.is_success() {
+            Ok(Download {
+                response,
+                _permit: permit,
+            })
+        } else {
+            Err(Error::from_status(response.status, path))
+        }
     }
 
     pub fn fetch_student_list(&self) -> StudentListFetchBuilder {
@@ -244,9 +713,285 @@ impl Client {
         StudentDataFetchBuilder::new().client(self.clone())
     }
 
-    pub async fn fetch_student_netsoul<'a>(
+    pub fn fetch_planning(&self) -> PlanningFetchBuilder {
+        PlanningFetchBuilder::new().client(self.clone())
+    }
+
+    pub async fn fetch_module(
         &self,
-        login: &'a str,
+        year: u32,
+        module: &str,
+        instance: &str,
+    ) -> Result<response::ModuleDetail, Error> {
+        let url = format!("/module/{}/{}/{}/", year, module, instance);
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    pub async fn fetch_activity(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<response::ActivityDetail, Error> {
+        let url = format!("/module/{}/{}/{}/{}/", year, module, instance, activity);
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    /// Fetches the current autologin link of the authenticated student.
+    ///
+    /// The link is never read from nor stored in the cache, as it grants access to the account.
+    pub async fn fetch_autologin(&self) -> Result<response::AutologinLink, Error> {
+        let mut client = self.clone();
+        client.cache = None;
+        let response = client.make_request("/admin/autolog").await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    /// Generates a new autologin link, revoking the previous one.
+    ///
+    /// The new link replaces the one stored in the client's session, as well as the one its authenticator replays (if any),
+    /// so that re-authentication keeps working after the rotation.
+    pub async fn regenerate_autologin(&self) -> Result<response::AutologinLink, Error> {
+        self.make_post_request("/admin/autolog/generate", &[])
+            .await?;
+        let link = self.fetch_autologin().await?;
+        {
+            let mut authenticator = self.authenticator.write().unwrap();
+            let replays_autologin = authenticator
+                .as_ref()
+                .is_some_and(|authenticator| authenticator.autologin().is_some());
+            if replays_autologin {
+                let renewed = AutologinAuthenticator::new(link.autologin.clone());
+                *authenticator = Some(Arc::new(renewed));
+            }
+        }
+        self.session.write().unwrap().autologin = Some(link.autologin.clone());
+        Ok(link)
+    }
+
+    pub async fn register_module(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+    ) -> Result<(), Error> {
+        let url = format!("/module/{}/{}/{}/register", year, module, instance);
+        self.make_post_request(url, &[]).await?;
+        Ok(())
+    }
+
+    pub async fn unregister_module(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+    ) -> Result<(), Error> {
+        let url = format!("/module/{}/{}/{}/unregister", year, module, instance);
+        self.make_post_request(url, &[]).await?;
+        Ok(())
+    }
+
+    pub async fn register_event(&self, event: &EventRef) -> Result<(), Error> {
+        let url = format!("{}/register", event.path());
+        self.make_post_request(url, &[]).await?;
+        Ok(())
+    }
+
+    pub async fn unregister_event(&self, event: &EventRef) -> Result<(), Error> {
+        let url = format!("{}/unregister", event.path());
+        self.make_post_request(url, &[]).await?;
+        Ok(())
+    }
+
+    pub async fn fetch_project(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<response::ProjectDetail, Error> {
+        let url = format!(
+            "/module/{}/{}/{}/{}/project/",
+            year, module, instance, activity
+        );
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    pub async fn fetch_project_files(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<Vec<response::ProjectFile>, Error> {
+        let url = format!(
+            "/module/{}/{}/{}/{}/project/file/",
+            year, module, instance, activity
+        );
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    pub async fn fetch_project_marks(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<Vec<response::ProjectMark>, Error> {
+        let url = format!(
+            "/module/{}/{}/{}/{}/project/note/",
+            year, module, instance, activity
+        );
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    /// Fetches the marks of every group for the activity of a `UserNotesMark`
+    /// (as obtained from `fetch_student_notes`).
+    pub async fn fetch_mark_detail(
+        &self,
+        mark: &response::UserNotesMark,
+    ) -> Result<response::MarkDetail, Error> {
+        let marks = self
+            .fetch_project_marks(
+                mark.scolaryear,
+                mark.codemodule.as_str(),
+                mark.codeinstance.as_str(),
+                mark.codeacti.as_str(),
+            )
+            .await?;
+        Ok(response::MarkDetail {
+            mark: mark.clone(),
+            marks,
+        })
+    }
+
+    /// Submits the attendance token of an event.
+    ///
+    /// The refusals of the intranet that are expected when validating a token
+    /// (wrong token, already validated, closed validation window) are reported as a `TokenValidation`,
+    /// any other refusal results in an error.
+    pub async fn validate_event_token(
+        &self,
+        event: &EventRef,
+        token: &str,
+    ) -> Result<response::TokenValidation, Error> {
+        let url = format!("{}/token", event.path());
+        let (status, body) = self
+            .send_post(&url, &[("token", token), ("rate", "1")])
+            .await?;
+        // The intranet refuses tokens with a successful status, so the body is checked regardless.
+        if (200..300).contains(&status) && !error::is_error_reply(&body) {
+            self.invalidate(&url);
+            return Ok(response::TokenValidation::Accepted);
+        }
+        match response::TokenValidation::from_refusal(&body) {
+            Some(outcome) => Ok(outcome),
+            None => Err(Error::from_intranet_failure(status, &url, &body)),
+        }
+    }
+
+    /// Registers a group to a project.
+    ///
+    /// The authenticated student is the group's master, `members` lists the logins of the other members.
+    pub async fn register_project(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+        members: &[&str],
+    ) -> Result<(), Error> {
+        let url = format!(
+            "/module/{}/{}/{}/{}/project/register",
+            year, module, instance, activity
+        );
+        let login = self.login();
+        let mut form = vec![("title", login.as_str())];
+        form.extend(members.iter().map(|member| ("members[]", *member)));
+        self.make_post_request(url, &form).await?;
+        Ok(())
+    }
+
+    pub async fn unregister_project(
+        &self,
+        year: u32,
+        module: &str,
+        instance: &str,
+        activity: &str,
+    ) -> Result<(), Error> {
+        let url = format!(
+            "/module/{}/{}/{}/{}/project/destroygroup",
+            year, module, instance, activity
+        );
+        self.make_post_request(url, &[]).await?;
+        Ok(())
+    }
+
+    pub fn fetch_many_student_data<I, T>(&self, logins: I) -> BulkFetchBuilder<response::UserData>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let logins = logins.into_iter().map(Into::into).collect();
+        BulkFetchBuilder::new(self.clone(), logins, |client, login| {
+            async move { client.fetch_student_data().login(login).send().await }.boxed()
+        })
+    }
+
+    pub fn fetch_many_student_netsoul<I, T>(
+        &self,
+        logins: I,
+    ) -> BulkFetchBuilder<Vec<response::UserNetsoulEntry>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let logins = logins.into_iter().map(Into::into).collect();
+        BulkFetchBuilder::new(self.clone(), logins, |client, login| {
+            async move { client.fetch_student_netsoul(login.as_str()).await }.boxed()
+        })
+    }
+
+    pub fn fetch_many_student_notes<I, T>(&self, logins: I) -> BulkFetchBuilder<response::UserNotes>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let logins = logins.into_iter().map(Into::into).collect();
+        BulkFetchBuilder::new(self.clone(), logins, |client, login| {
+            async move { client.fetch_student_notes(login.as_str()).await }.boxed()
+        })
+    }
+
+    pub fn fetch_many_student_binomes<I, T>(
+        &self,
+        logins: I,
+    ) -> BulkFetchBuilder<response::UserBinome>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let logins = logins.into_iter().map(Into::into).collect();
+        BulkFetchBuilder::new(self.clone(), logins, |client, login| {
+            async move { client.fetch_student_binomes(login.as_str()).await }.boxed()
+        })
+    }
+
+    pub async fn fetch_student_netsoul(
+        &self,
+        login: &str,
     ) -> Result<Vec<response::UserNetsoulEntry>, Error> {
         let url = format!("/user/{}/netsoul", login);
         let response = self.make_request(url).await?;
@@ -257,13 +1002,10 @@ impl Client {
     pub async fn fetch_own_student_netsoul(
         &self,
     ) -> Result<Vec<response::UserNetsoulEntry>, Error> {
-        self.fetch_student_netsoul(self.login.as_ref()).await
+        self.fetch_student_netsoul(&self.login()).await
     }
 
-    pub async fn fetch_student_notes<'a>(
-        &self,
-        login: &'a str,
-    ) -> Result<response::UserNotes, Error> {
+    pub async fn fetch_student_notes(&self, login: &str) -> Result<response::UserNotes, Error> {
         let url = format!("/user/{}/notes", login);
         let response = self.make_request(url).await?;
         let data = json::from_str(&response)?;
@@ -271,13 +1013,10 @@ impl Client {
     }
 
     pub async fn fetch_own_student_notes(&self) -> Result<response::UserNotes, Error> {
-        self.fetch_student_notes(self.login.as_ref()).await
+        self.fetch_student_notes(&self.login()).await
     }
 
-    pub async fn fetch_student_binomes<'a>(
-        &self,
-        login: &'a str,
-    ) -> Result<response::UserBinome, Error> {
+    pub async fn fetch_student_binomes(&self, login: &str) -> Result<response::UserBinome, Error> {
         let url = format!("/user/{}/binome", login);
         let response = self.make_request(url).await?;
         let data = json::from_str(&response)?;
@@ -285,7 +1024,72 @@ impl Client {
     }
 
     pub async fn fetch_own_student_binomes(&self) -> Result<response::UserBinome, Error> {
-        self.fetch_student_binomes(self.login.as_ref()).await
+        self.fetch_student_binomes(&self.login()).await
+    }
+
+    pub async fn fetch_student_notification_messages(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationMessage>, Error> {
+        let url = format!("/user/{}/notification/message", login);
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    pub async fn fetch_own_student_notification_messages(
+        &self,
+    ) -> Result<Vec<response::NotificationMessage>, Error> {
+        self.fetch_student_notification_messages(&self.login())
+            .await
+    }
+
+    pub async fn fetch_student_notification_alerts(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationAlert>, Error> {
+        let url = format!("/user/{}/notification/alert", login);
+        let response = self.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
+    pub async fn fetch_own_student_notification_alerts(
+        &self,
+    ) -> Result<Vec<response::NotificationAlert>, Error> {
+        self.fetch_student_notification_alerts(&self.login()).await
+    }
+
+    pub async fn fetch_student_notification_missed(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        let url = format!("/user/{}/notification/missed", login);
+        let response = self.make_request(url).await?;
+        let data = json::from_str::<NotificationEvents>(&response)?;
+        Ok(data.events)
+    }
+
+    pub async fn fetch_own_student_notification_missed(
+        &self,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        self.fetch_student_notification_missed(&self.login()).await
+    }
+
+    pub async fn fetch_student_notification_coming(
+        &self,
+        login: &str,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        let url = format!("/user/{}/notification/coming", login);
+        let response = self.make_request(url).await?;
+        let data = json::from_str::<NotificationEvents>(&response)?;
+        Ok(data.events)
+    }
+
+    pub async fn fetch_own_student_notification_coming(
+        &self,
+    ) -> Result<Vec<response::NotificationEvent>, Error> {
+        self.fetch_student_notification_coming(&self.login()).await
     }
 
     pub async fn search_student(
@@ -298,6 +1102,15 @@ impl Client {
         Ok(data)
     }
 
+    /// Fetches the campuses having active students, including the ones unknown to `Location`.
+    pub async fn fetch_locations(&self) -> Result<Vec<response::LocationEntry>, Error> {
+        let response = self
+            .make_request("/user/filter/location?active=true")
+            .await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
     pub async fn fetch_available_courses(
         &self,
         location: Location,
@@ -334,10 +1147,87 @@ impl Default for Client {
     #[inline]
     fn default() -> Client {
         Client {
-            autologin: String::default(),
-            retry_count: 5,
-            client: reqwest::Client::new(),
-            login: String::default(),
+            endpoint: String::from(ENDPOINT),
+            session: Arc::new(RwLock::new(Session {
+                login: String::default(),
+                cookie: String::default(),
+                autologin: None,
+                expires: None,
+            })),
+            authenticator: Arc::default(),
+            retry_policy: RetryPolicy::default(),
+            limiter: Limiter::default(),
+            cache: None,
+            cache_policy: CachePolicy::default(),
+            bypass_cache: false,
+            // Like `reqwest::Client::new`, this panics if the HTTP client cannot be initialized.
+            transport: Arc::new(
+                ReqwestTransport::new().expect("could not initialize the HTTP client"),
+            ),
+            renewal: Arc::default(),
+        }
+    }
+}
+
+impl Download {
+    /// The MIME type of the file, as announced by the intranet.
+    pub fn content_type(&self) -> Option<&str> {
+        self.response.header_value(header::CONTENT_TYPE.as_str())
+    }
+
+    /// The size of the file in bytes, if announced by the intranet.
+    pub fn content_length(&self) -> Option<u64> {
+        let length = self
+            .response
+            .header_value(header::CONTENT_LENGTH.as_str())?;
+        length.parse().ok()
+    }
+
+    /// Streams the file into `writer`, returning the number of bytes written.
+    pub async fn write_to<W: AsyncWrite + Unpin>(mut self, writer: &mut W) -> Result<u64, Error> {
+        let mut written = 0;
+        while let Some(chunk) = self.response.chunk().await? {
+            writer
+                .write_all(&chunk)
+                .await
+                .map_err(|err| Error::IoError(err.to_string()))?;
+            written += chunk.len() as u64;
+        }
+        writer
+            .flush()
+            .await
+            .map_err(|err| Error::IoError(err.to_string()))?;
+        Ok(written)
+    }
+
+    /// Reads the whole file in memory.
+    pub async fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = self.response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+impl EventRef {
+    /// The intranet path of the event (eg. `/module/2019/B-PRO-300/STG-3-1/acti-123/event-456`).
+    pub fn path(&self) -> String {
+        format!(
+            "/module/{}/{}/{}/{}/{}",
+            self.scolaryear, self.codemodule, self.codeinstance, self.codeacti, self.codeevent
+        )
+    }
+}
+
+impl From<&response::PlanningEvent> for EventRef {
+    fn from(event: &response::PlanningEvent) -> EventRef {
+        EventRef {
+            scolaryear: event.scolaryear,
+            codemodule: event.codemodule.clone(),
+            codeinstance: event.codeinstance.clone(),
+            codeacti: event.codeacti.clone(),
+            codeevent: event.codeevent.clone(),
         }
     }
 }
@@ -351,28 +1241,33 @@ impl StudentListFetchBuilder {
             promo: None,
             active: true,
             offset: 0,
-            year: Local::now().date().year() as u32,
+            year: Local::now().year() as u32,
             course: None,
         }
     }
 
+    async fn fetch_page(&self) -> Result<UserEntries, Error> {
+        let mut url = format!(
+            "/user/filter/user?offset={}&year={}&active={}",
+            self.offset, self.year, self.active,
+        );
+        if let Some(ref location) = self.location {
+            url = format!("{}&location={}", url, location);
+        }
+        if let Some(ref promo) = self.promo {
+            url = format!("{}&promo={}", url, promo);
+        }
+        if let Some(ref course) = self.course {
+            url = format!("{}&course={}", url, course);
+        }
+        let response = self.client.make_request(url).await?;
+        let data = json::from_str(&response)?;
+        Ok(data)
+    }
+
     fn send_impl(self) -> Pin<Box<dyn Future<Output = Result<Vec<response::UserEntry>, Error>>>> {
         Box::pin(async move {
-            let mut url = format!(
-                "/user/filter/user?offset={}&year={}&active={}",
-                self.offset, self.year, self.active,
-            );
-            if let Some(ref location) = self.location {
-                url = format!("{}&location={}", url, location);
-            }
-            if let Some(ref promo) = self.promo {
-                url = format!("{}&promo={}", url, promo);
-            }
-            if let Some(ref course) = self.course {
-                url = format!("{}&course={}", url, course);
-            }
-            let response = self.client.make_request(url).await?;
-            let mut data = json::from_str::<UserEntries>(&response)?;
+            let mut data = self.fetch_page().await?;
             let state: usize = (self.offset as usize) + data.items.len();
             if state == data.total {
                 Ok(data.items)
@@ -390,6 +1285,31 @@ impl StudentListFetchBuilder {
         self.send_impl().await
     }
 
+    /// Lazily fetches the student list, one page at a time.
+    ///
+    /// The next page is only requested once every entry of the current one has been consumed,
+    /// so dropping the stream stops the pagination.
+    /// Unlike `send`, changes of the total number of students during the pagination are tolerated:
+    /// the stream ends as soon as a page comes back empty or the latest announced total is reached.
+    pub fn stream(self) -> impl Stream<Item = Result<response::UserEntry, Error>> {
+        let pages = stream::try_unfold(Some(self), |builder| async move {
+            let builder = match builder {
+                Some(builder) => builder,
+                None => return Ok::<_, Error>(None),
+            };
+            let data = builder.fetch_page().await?;
+            let next = (builder.offset as usize) + data.items.len();
+            let next = if data.items.is_empty() || next >= data.total {
+                None
+            } else {
+                Some(builder.offset(next as u32))
+            };
+            let entries = stream::iter(data.items.into_iter().map(Ok));
+            Ok(Some((entries, next)))
+        });
+        pages.try_flatten()
+    }
+
     #[inline]
     pub fn client(mut self, client: Client) -> StudentListFetchBuilder {
         self.client = client;
@@ -465,30 +1385,143 @@ impl StudentDataFetchBuilder {
     }
 }
 
+impl PlanningFetchBuilder {
+    #[inline]
+    pub fn new() -> PlanningFetchBuilder {
+        let start = Local::now().naive_local().date();
+        PlanningFetchBuilder {
+            client: Client::default(),
+            start,
+            end: start + chrono::Duration::days(7),
+            semesters: Vec::default(),
+            only_registered: false,
+            only_my_modules: false,
+        }
+    }
+
+    pub async fn send(self) -> Result<Vec<response::PlanningEvent>, Error> {
+        let url = format!(
+            "/planning/load?start={}&end={}",
+            self.start.format("%Y-%m-%d"),
+            self.end.format("%Y-%m-%d"),
+        );
+        let response = self.client.make_request(url).await?;
+        let data = json::from_str::<Vec<response::PlanningEvent>>(&response)?;
+        let data = data
+            .into_iter()
+            .filter(|event| {
+                self.semesters.is_empty()
+                    || event
+                        .semester
+                        .is_some_and(|semester| self.semesters.contains(&semester))
+            })
+            .filter(|event| {
+                !self.only_registered
+                    || event.event_registered != response::EventRegistration::Unregistered
+            })
+            .filter(|event| !self.only_my_modules || event.module_registered)
+            .collect();
+        Ok(data)
+    }
+
+    #[inline]
+    pub fn client(mut self, client: Client) -> PlanningFetchBuilder {
+        self.client = client;
+        self
+    }
+
+    #[inline]
+    pub fn start(mut self, start: NaiveDate) -> PlanningFetchBuilder {
+        self.start = start;
+        self
+    }
+
+    #[inline]
+    pub fn end(mut self, end: NaiveDate) -> PlanningFetchBuilder {
+        self.end = end;
+        self
+    }
+
+    #[inline]
+    pub fn semester(mut self, semester: u32) -> PlanningFetchBuilder {
+        self.semesters.push(semester);
+        self
+    }
+
+    #[inline]
+    pub fn only_registered(mut self, only_registered: bool) -> PlanningFetchBuilder {
+        self.only_registered = only_registered;
+        self
+    }
+
+    #[inline]
+    pub fn only_my_modules(mut self, only_my_modules: bool) -> PlanningFetchBuilder {
+        self.only_my_modules = only_my_modules;
+        self
+    }
+}
+
+impl Default for PlanningFetchBuilder {
+    #[inline]
+    fn default() -> PlanningFetchBuilder {
+        PlanningFetchBuilder::new()
+    }
+}
+
+impl Location {
+    /// Every campus known to this library (replacing `Location::into_enum_iter`).
+    pub fn all_known() -> impl Iterator<Item = Location> {
+        KNOWN_LOCATIONS.iter().cloned()
+    }
+}
+
+const KNOWN_LOCATIONS: &[Location] = &[
+    Location::Barcelone,
+    Location::Berlin,
+    Location::Bordeaux,
+    Location::LaReunion,
+    Location::Lille,
+    Location::Lyon,
+    Location::Marseille,
+    Location::Montpellier,
+    Location::Nancy,
+    Location::Nantes,
+    Location::Nice,
+    Location::Paris,
+    Location::Rennes,
+    Location::Strasbourg,
+    Location::Toulouse,
+    Location::Cotonou,
+    Location::Tirana,
+    Location::Bruxelles,
+];
+
 impl FromStr for Location {
-    type Err = ();
+    type Err = ParseLocationError;
     fn from_str(string: &str) -> Result<Self, Self::Err> {
-        match string {
-            "ES/BAR" => Ok(Location::Barcelone),
-            "DE/BER" => Ok(Location::Berlin),
-            "FR/BDX" => Ok(Location::Bordeaux),
-            "FR/RUN" => Ok(Location::LaReunion),
-            "FR/LIL" => Ok(Location::Lille),
-            "FR/LYN" => Ok(Location::Lyon),
-            "FR/MAR" => Ok(Location::Marseille),
-            "FR/MPL" => Ok(Location::Montpellier),
-            "FR/NCY" => Ok(Location::Nancy),
-            "FR/NAN" => Ok(Location::Nantes),
-            "FR/NCE" => Ok(Location::Nice),
-            "FR/PAR" => Ok(Location::Paris),
-            "FR/REN" => Ok(Location::Rennes),
-            "FR/STG" => Ok(Location::Strasbourg),
-            "FR/TLS" => Ok(Location::Toulouse),
-            "BJ/COT" => Ok(Location::Cotonou),
-            "AL/TIR" => Ok(Location::Tirana),
-            "BE/BRU" => Ok(Location::Bruxelles),
-            _ => Err(()),
-        }
+        let value = match string {
+            "ES/BAR" => Location::Barcelone,
+            "DE/BER" => Location::Berlin,
+            "FR/BDX" => Location::Bordeaux,
+            "FR/RUN" => Location::LaReunion,
+            "FR/LIL" => Location::Lille,
+            "FR/LYN" => Location::Lyon,
+            "FR/MAR" => Location::Marseille,
+            "FR/MPL" => Location::Montpellier,
+            "FR/NCY" => Location::Nancy,
+            "FR/NAN" => Location::Nantes,
+            "FR/NCE" => Location::Nice,
+            "FR/PAR" => Location::Paris,
+            "FR/REN" => Location::Rennes,
+            "FR/STG" => Location::Strasbourg,
+            "FR/TLS" => Location::Toulouse,
+            "BJ/COT" => Location::Cotonou,
+            "AL/TIR" => Location::Tirana,
+            "BE/BRU" => Location::Bruxelles,
+            _ if is_location_code(string) => Location::Other(string.to_string()),
+            _ => return Err(ParseLocationError(string.to_string())),
+        };
+        Ok(value)
     }
 }
 
@@ -513,24 +1546,58 @@ impl fmt::Display for Location {
             Location::Bruxelles => "BE/BRU",
             Location::Cotonou => "BJ/COT",
             Location::Tirana => "AL/TIR",
+            Location::Other(code) => code.as_str(),
         };
         write!(f, "{}", repr)
     }
 }
 
+impl Serialize for Location {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Location {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Intranet responses are trusted as is, only user input gets validated (by `FromStr`).
+        let string = String::deserialize(deserializer)?;
+        Ok(string.parse().unwrap_or(Location::Other(string)))
+    }
+}
+
+impl Promo {
+    /// Every promotion known to this library (replacing `Promo::into_enum_iter`).
+    pub fn all_known() -> impl Iterator<Item = Promo> {
+        KNOWN_PROMOS.iter().cloned()
+    }
+}
+
+const KNOWN_PROMOS: &[Promo] = &[
+    Promo::Tek1,
+    Promo::Tek2,
+    Promo::Tek3,
+    Promo::Wac1,
+    Promo::Wac2,
+    Promo::Msc3,
+    Promo::Msc4,
+];
+
 impl FromStr for Promo {
-    type Err = ();
+    type Err = ParsePromoError;
     fn from_str(string: &str) -> Result<Self, Self::Err> {
-        match string {
-            "tek1" => Ok(Promo::Tek1),
-            "tek2" => Ok(Promo::Tek2),
-            "tek3" => Ok(Promo::Tek3),
-            "wac1" => Ok(Promo::Wac1),
-            "wac2" => Ok(Promo::Wac2),
-            "msc3" => Ok(Promo::Msc3),
-            "msc4" => Ok(Promo::Msc4),
-            _ => Err(()),
-        }
+        let value = match string {
+            "tek1" => Promo::Tek1,
+            "tek2" => Promo::Tek2,
+            "tek3" => Promo::Tek3,
+            "wac1" => Promo::Wac1,
+            "wac2" => Promo::Wac2,
+            "msc3" => Promo::Msc3,
+            "msc4" => Promo::Msc4,
+            _ if is_promo_code(string) => Promo::Other(string.to_string()),
+            _ => return Err(ParsePromoError(string.to_string())),
+        };
+        Ok(value)
     }
 }
 
@@ -544,7 +1611,41 @@ impl fmt::Display for Promo {
             Promo::Wac2 => "wac2",
             Promo::Msc3 => "msc3",
             Promo::Msc4 => "msc4",
+            Promo::Other(code) => code.as_str(),
         };
         write!(f, "{}", repr)
     }
 }
+
+impl Serialize for Promo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Promo {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Intranet responses are trusted as is, only user input gets validated (by `FromStr`).
+        let string = String::deserialize(deserializer)?;
+        Ok(string.parse().unwrap_or(Promo::Other(string)))
+    }
+}
+
+/// Whether `string` looks like a location code, made of a country and a city part (eg. `FR/PAR`).
+fn is_location_code(string: &str) -> bool {
+    let mut parts = string.split('/');
+    let valid_part =
+        |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric());
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some(country), Some(city), None) if valid_part(country) && valid_part(city)
+    )
+}
+
+/// Whether `string` looks like a promo code (eg. `tek1`).
+fn is_promo_code(string: &str) -> bool {
+    !string.is_empty()
+        && string
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Throttles the requests sent by a `Client` and all of its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Limiter {
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl Limiter {
+    pub(crate) fn new(
+        rate_limit: Option<(u32, Duration)>,
+        max_in_flight: Option<usize>,
+    ) -> Limiter {
+        let bucket = rate_limit
+            .filter(|(requests, per)| *requests > 0 && *per > Duration::from_secs(0))
+            .map(|(requests, per)| {
+                let capacity = f64::from(requests);
+                Arc::new(Mutex::new(TokenBucket {
+                    capacity,
+                    tokens: capacity,
+                    refill_per_sec: capacity / per.as_secs_f64(),
+                    last_refill: Instant::now(),
+                }))
+            });
+        let semaphore = max_in_flight
+            .filter(|max| *max > 0)
+            .map(|max| Arc::new(Semaphore::new(max)));
+        Limiter { bucket, semaphore }
+    }
+
+    /// Waits until a request is allowed to be sent.
+    ///
+    /// The returned permit (if any) must be held for as long as the request is in flight.
+    pub(crate) async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = match self.semaphore {
+            Some(ref semaphore) => Some(Arc::clone(semaphore).acquire_owned().await),
+            None => None,
+        };
+        if let Some(ref bucket) = self.bucket {
+            loop {
+                let wait = bucket.lock().unwrap().try_take();
+                match wait {
+                    None => break,
+                    Some(wait) => tokio::time::delay_for(wait).await,
+                }
+            }
+        }
+        permit
+    }
+}
+
+impl TokenBucket {
+    /// Takes a token if one is available, otherwise returns how long to wait for the next one.
+    fn try_take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            let missing = 1.0 - self.tokens;
+            Some(Duration::from_secs_f64(missing / self.refill_per_sec))
+        }
+    }
+}
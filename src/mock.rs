@@ -0,0 +1,470 @@
+//! An in-process mock of the intranet, serving recorded JSON fixtures.
+//!
+//! It is used by the test suite to run offline, and is available to dependent crates behind the `mock` feature:
+//!
+//! ```ignore
+//! let intranet = MockIntranet::start();
+//! let client = intranet.client().authenticate().await?;
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use hyper::header;
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+
+use crate::{Client, ClientBuilder};
+
+/// The number of students served per page by `/user/filter/user`, unless changed with `MockIntranet::page_size`.
+const PAGE_SIZE: usize = 2;
+
+/// The recorded responses, by route.
+/// `{login}` segments match the login of any known student, which gets substituted into the response.
+const FIXTURES: &[(&str, &str)] = &[
+    ("/user/{login}", include_str!("../fixtures/user.json")),
+    (
+        "/user/{login}/netsoul",
+        include_str!("../fixtures/netsoul.json"),
+    ),
+    (
+        "/user/{login}/notes",
+        include_str!("../fixtures/notes.json"),
+    ),
+    (
+        "/user/{login}/binome",
+        include_str!("../fixtures/binome.json"),
+    ),
+    (
+        "/user/{login}/notification/message",
+        include_str!("../fixtures/notification_message.json"),
+    ),
+    (
+        "/user/{login}/notification/alert",
+        include_str!("../fixtures/notification_alert.json"),
+    ),
+    (
+        "/user/{login}/notification/missed",
+        include_str!("../fixtures/notification_missed.json"),
+    ),
+    (
+        "/user/{login}/notification/coming",
+        include_str!("../fixtures/notification_coming.json"),
+    ),
+    (
+        "/user/filter/user",
+        include_str!("../fixtures/user_filter_user.json"),
+    ),
+    (
+        "/user/filter/location",
+        include_str!("../fixtures/user_filter_location.json"),
+    ),
+    (
+        "/user/filter/course",
+        include_str!("../fixtures/user_filter_course.json"),
+    ),
+    (
+        "/user/filter/promo",
+        include_str!("../fixtures/user_filter_promo.json"),
+    ),
+    (
+        "/complete/user",
+        include_str!("../fixtures/complete_user.json"),
+    ),
+    ("/planning/load", include_str!("../fixtures/planning.json")),
+    (
+        "/module/2019/B-PRO-300/STG-3-1",
+        include_str!("../fixtures/module.json"),
+    ),
+    (
+        "/module/2019/B-PRO-300/STG-3-1/acti-360401",
+        include_str!("../fixtures/activity.json"),
+    ),
+    (
+        "/module/2019/B-CPE-110/STG-1-1/acti-350001/project",
+        include_str!("../fixtures/project.json"),
+    ),
+    (
+        "/module/2019/B-CPE-110/STG-1-1/acti-350001/project/file",
+        include_str!("../fixtures/project_files.json"),
+    ),
+    (
+        "/module/2019/B-CPE-110/STG-1-1/acti-350001/project/note",
+        include_str!("../fixtures/project_marks.json"),
+    ),
+    ("/admin/autolog", include_str!("../fixtures/autolog.json")),
+];
+
+/// The recorded files, by route.
+const FILES: &[(&str, &str, &[u8])] = &[
+    (
+        "/file/userprofil/profilview/mock.student.bmp",
+        "image/bmp",
+        include_bytes!("../fixtures/picture.bmp"),
+    ),
+    (
+        "/module/2019/B-CPE-110/STG-1-1/acti-350001/project/file/bsq.pdf",
+        "application/pdf",
+        include_bytes!("../fixtures/bsq.pdf"),
+    ),
+];
+
+/// A mock intranet listening on a local port.
+///
+/// It authenticates a single student (`MockIntranet::LOGIN`) through an autologin link,
+/// and rejects requests that do not carry the session cookie it hands out.
+///
+/// Registrations (to modules, events and projects) and event tokens are accepted for any activity,
+/// unless a refusal was set up using `MockIntranet::refuse`.
+#[derive(Clone)]
+pub struct MockIntranet {
+    url: String,
+    state: Arc<MockState>,
+}
+
+struct MockState {
+    url: String,
+    cookie: Mutex<String>,
+    token: Mutex<String>,
+    students: Vec<String>,
+    routes: Mutex<HashMap<String, String>>,
+    refusals: Mutex<HashMap<String, (u16, String)>>,
+    registrations: Mutex<HashSet<String>>,
+    validated: Mutex<HashSet<String>>,
+    page_size: Mutex<usize>,
+    requests: Mutex<HashMap<String, usize>>,
+}
+
+impl MockIntranet {
+    /// The login of the student authenticated by the mock.
+    pub const LOGIN: &'static str = "first.last@epitech.eu";
+
+    /// The attendance token accepted for every event.
+    pub const TOKEN: &'static str = "12345678";
+
+    /// Starts the mock on a random local port.
+    ///
+    /// This must be called from within a Tokio runtime, which the server runs on until it shuts down.
+    pub fn start() -> MockIntranet {
+        let routes: HashMap<String, String> = FIXTURES
+            .iter()
+            .map(|(route, body)| (route.to_string(), body.to_string()))
+            .collect();
+        let mut students: Vec<String> = json::from_str::<json::Value>(&routes["/user/filter/user"])
+            .ok()
+            .and_then(|list| {
+                let items = list.get("items")?.as_array()?.iter();
+                let logins =
+                    items.filter_map(|item| Some(item.get("login")?.as_str()?.to_string()));
+                Some(logins.collect())
+            })
+            .unwrap_or_default();
+        students.push(String::from(MockIntranet::LOGIN));
+
+        let mut incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into())
+            .expect("could not bind the mock intranet to a local port");
+        incoming.set_nodelay(true);
+        let url = format!("http://{}", incoming.local_addr());
+        let state = Arc::new(MockState {
+            url: url.clone(),
+            cookie: Mutex::new(String::from("mock-session-cookie")),
+            token: Mutex::new(String::from("0123456789abcdef")),
+            students,
+            routes: Mutex::new(routes),
+            refusals: Mutex::default(),
+            registrations: Mutex::default(),
+            validated: Mutex::default(),
+            page_size: Mutex::new(PAGE_SIZE),
+            requests: Mutex::default(),
+        });
+
+        let service_state = state.clone();
+        let server = Server::builder(incoming).serve(make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(state.handle(request).await) }
+                }))
+            }
+        }));
+        tokio::spawn(server);
+
+        MockIntranet { url, state }
+    }
+
+    /// The base URL of the mock, to pass to `ClientBuilder::endpoint`.
+    #[inline]
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// The current autologin link of the mocked student.
+    pub fn autologin(&self) -> String {
+        self.state.autologin()
+    }
+
+    /// A client builder targeting the mock, using its autologin link.
+    pub fn client(&self) -> ClientBuilder {
+        Client::builder()
+            .endpoint(self.url())
+            .autologin(self.autologin())
+    }
+
+    /// Serves `body` for `route` (eg. `/module/2019/B-PRO-300/STG-3-1`), replacing the recorded response if any.
+    pub fn route<R: Into<String>, B: Into<String>>(&self, route: R, body: B) {
+        let route = route.into().trim_end_matches('/').to_string();
+        self.state.routes.lock().unwrap().insert(route, body.into());
+    }
+
+    /// Answers the requests to `route` (eg. `/module/2019/B-PRO-300/STG-3-1/register`)
+    /// with `status` and `body`, the way the intranet refuses a registration.
+    pub fn refuse<R: Into<String>, B: Into<String>>(&self, route: R, status: u16, body: B) {
+        let route = route.into().trim_end_matches('/').to_string();
+        let mut refusals = self.state.refusals.lock().unwrap();
+        refusals.insert(route, (status, body.into()));
+    }
+
+    /// Expires the session cookie handed out so far, as the intranet does after a while.
+    ///
+    /// The requests carrying it are answered with `401 Unauthorized`, and the autologin link hands out a new one.
+    pub fn expire_session(&self) {
+        let mut cookie = self.state.cookie.lock().unwrap();
+        *cookie = format!("mock-session-{:016x}", rand::random::<u64>());
+    }
+
+    /// Sets the number of students served per page by `/user/filter/user`.
+    pub fn page_size(&self, page_size: usize) {
+        *self.state.page_size.lock().unwrap() = page_size;
+    }
+
+    /// The number of requests received so far for `route` (eg. `/user/filter/user`), whatever their query.
+    pub fn requests(&self, route: &str) -> usize {
+        let route = route.trim_end_matches('/');
+        let requests = self.state.requests.lock().unwrap();
+        requests.get(route).copied().unwrap_or_default()
+    }
+
+    /// Whether the mocked student is registered to the module, event or project at `path`
+    /// (eg. `/module/2019/B-PRO-300/STG-3-1`).
+    pub fn is_registered(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        self.state.registrations.lock().unwrap().contains(path)
+    }
+}
+
+impl fmt::Debug for MockIntranet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MockIntranet")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl MockState {
+    fn autologin(&self) -> String {
+        format!("{}/auth-{}", self.url, self.token.lock().unwrap())
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let path = match request.uri().path().trim_end_matches('/') {
+            "" => "/",
+            path => path,
+        }
+        .to_string();
+        let path = path.as_str();
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default() += 1;
+
+        if let Some(token) = path.strip_prefix("/auth-") {
+            if token != *self.token.lock().unwrap() {
+                return status(StatusCode::FORBIDDEN);
+            }
+            let cookie = self.cookie.lock().unwrap();
+            let cookie = format!("user={}; Max-Age=3600; path=/", cookie);
+            return Response::builder()
+                .status(StatusCode::FOUND)
+                .header(header::LOCATION, "/")
+                .header(header::SET_COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap();
+        }
+
+        let expected = format!("user={}", self.cookie.lock().unwrap());
+        let authenticated = request
+            .headers()
+            .get(header::COOKIE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|cookies| cookies.split(';').any(|it| it.trim() == expected));
+        if !authenticated {
+            return status(StatusCode::UNAUTHORIZED);
+        }
+
+        if let Some((code, body)) = self.refusals.lock().unwrap().get(path) {
+            let code = StatusCode::from_u16(*code).unwrap_or(StatusCode::FORBIDDEN);
+            let mut response = json_response(body.clone());
+            *response.status_mut() = code;
+            return response;
+        }
+
+        match *request.method() {
+            Method::GET => {}
+            Method::POST => {
+                let form = hyper::body::to_bytes(request.into_body())
+                    .await
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .unwrap_or_default();
+                return self.handle_post(path, &form);
+            }
+            _ => return status(StatusCode::NOT_FOUND),
+        }
+
+        if let Some((_, content_type, bytes)) = FILES.iter().find(|(route, _, _)| *route == path) {
+            return Response::builder()
+                .header(header::CONTENT_TYPE, *content_type)
+                .header(header::CONTENT_LENGTH, bytes.len())
+                .body(Body::from(*bytes))
+                .unwrap();
+        }
+
+        // `/user` is the profile of the authenticated student.
+        let (route, login) = match path.split('/').nth(2) {
+            _ if path == "/user" => (String::from("/user/{login}"), MockIntranet::LOGIN),
+            Some(login) if path.starts_with("/user/") && login.contains('@') => {
+                if !self.students.iter().any(|student| student == login) {
+                    return status(StatusCode::NOT_FOUND);
+                }
+                (path.replacen(login, "{login}", 1), login)
+            }
+            _ => (path.to_string(), MockIntranet::LOGIN),
+        };
+        let body = match self.routes.lock().unwrap().get(&route) {
+            Some(body) => body
+                .replace("{login}", login)
+                .replace("{autologin}", &self.autologin()),
+            None => return status(StatusCode::NOT_FOUND),
+        };
+
+        if route == "/user/filter/user" {
+            let offset = request
+                .uri()
+                .query()
+                .unwrap_or_default()
+                .split('&')
+                .find_map(|param| param.strip_prefix("offset="))
+                .and_then(|offset| offset.parse().ok())
+                .unwrap_or(0);
+            let page_size = *self.page_size.lock().unwrap();
+            let body = paginate(&body, offset, page_size);
+            return conditional_response(&request, body);
+        }
+        conditional_response(&request, body)
+    }
+
+    fn handle_post(&self, path: &str, form: &str) -> Response<Body> {
+        if path == "/admin/autolog/generate" {
+            let mut token = self.token.lock().unwrap();
+            *token = format!("{:016x}", rand::random::<u64>());
+            return json_response(String::from("{}"));
+        }
+        if !path.starts_with("/module/") {
+            return status(StatusCode::NOT_FOUND);
+        }
+
+        let (target, action) = match path.rfind('/') {
+            Some(index) => (&path[..index], &path[index + 1..]),
+            None => return status(StatusCode::NOT_FOUND),
+        };
+        // Registering to a project also goes through `/project/register`, but leaving it is `/project/destroygroup`.
+        let mut registrations = self.registrations.lock().unwrap();
+        match action {
+            "register" if registrations.insert(target.to_string()) => {
+                json_response(String::from("{}"))
+            }
+            "register" => failure(StatusCode::FORBIDDEN, "You are already registered"),
+            "unregister" | "destroygroup" if registrations.remove(target) => {
+                json_response(String::from("{}"))
+            }
+            "unregister" | "destroygroup" => {
+                failure(StatusCode::FORBIDDEN, "You are not registered")
+            }
+            // The intranet answers token submissions with a successful status, even when refusing them.
+            "token" => {
+                let token = form
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("token="))
+                    .unwrap_or_default();
+                if token != MockIntranet::TOKEN {
+                    return failure(StatusCode::OK, "Invalid token");
+                }
+                if !self.validated.lock().unwrap().insert(target.to_string()) {
+                    return failure(StatusCode::OK, "You have already validated this token");
+                }
+                json_response(String::from("{}"))
+            }
+            _ => status(StatusCode::NOT_FOUND),
+        }
+    }
+}
+
+/// Serves one page of a `{ "total": _, "items": [..] }` list, starting at `offset`.
+fn paginate(body: &str, offset: usize, page_size: usize) -> String {
+    let mut list: json::Value = match json::from_str(body) {
+        Ok(list) => list,
+        Err(_) => return body.to_string(),
+    };
+    if let Some(items) = list.get_mut("items").and_then(|items| items.as_array_mut()) {
+        let page: Vec<_> = items.iter().skip(offset).take(page_size).cloned().collect();
+        *items = page;
+    }
+    list.to_string()
+}
+
+/// Serves `body` with an `ETag`, or answers `304 Not Modified` if the request already holds it.
+fn conditional_response(request: &Request<Body>, body: String) -> Response<Body> {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let etag = format!("\"{:016x}\"", hasher.finish());
+    let cached = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|value| value.as_bytes() == etag.as_bytes());
+    let mut response = if cached {
+        status(StatusCode::NOT_MODIFIED)
+    } else {
+        json_response(body)
+    };
+    let etag = header::HeaderValue::from_str(&etag).unwrap();
+    response.headers_mut().insert(header::ETAG, etag);
+    response
+}
+
+fn json_response(body: String) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// An error reply, in the format of the intranet.
+fn failure(code: StatusCode, message: &str) -> Response<Body> {
+    let mut response = json_response(json::json!({ "error": message }).to_string());
+    *response.status_mut() = code;
+    response
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
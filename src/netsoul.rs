@@ -0,0 +1,77 @@
+//! Aggregation helpers over the log time entries returned by `Client::fetch_student_netsoul`.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::response::{UserDataNsStat, UserNetsoulEntry};
+
+/// The log time of a student summed over one week (from monday to sunday), in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct NetsoulWeek {
+    #[serde(with = "crate::serde_utils::date")]
+    pub start: NaiveDate,
+    pub active: f64,
+    pub idle: f64,
+    pub out_active: f64,
+    pub out_idle: f64,
+}
+
+impl NetsoulWeek {
+    /// The active log time of the week, in hours.
+    #[inline]
+    pub fn active_hours(&self) -> f64 {
+        self.active / 3600.0
+    }
+
+    /// The ratio between the active log time of the week and the expected weekly log time
+    /// (`UserDataNsStat::nslog_norm`, in hours), where `1.0` means the norm is exactly met.
+    /// Returns `None` if the student has no norm.
+    pub fn norm_ratio(&self, nsstat: &UserDataNsStat) -> Option<f64> {
+        if nsstat.nslog_norm > 0.0 {
+            Some(self.active_hours() / f64::from(nsstat.nslog_norm))
+        } else {
+            None
+        }
+    }
+}
+
+/// Sums the daily entries by week, in chronological order.
+pub fn weekly_totals(entries: &[UserNetsoulEntry]) -> Vec<NetsoulWeek> {
+    let mut weeks = BTreeMap::new();
+    for entry in entries {
+        let offset = entry.date.weekday().num_days_from_monday();
+        let start = entry.date - Duration::days(i64::from(offset));
+        let week = weeks.entry(start).or_insert_with(|| NetsoulWeek {
+            start,
+            ..NetsoulWeek::default()
+        });
+        week.active += entry.active;
+        week.idle += entry.idle;
+        week.out_active += entry.out_active;
+        week.out_idle += entry.out_idle;
+    }
+    weeks.into_values().collect()
+}
+
+/// Computes, for each entry, the average active log time (in seconds) over the last `window` days.
+///
+/// Days for which no entry exists are counted as zero.
+pub fn rolling_average(entries: &[UserNetsoulEntry], window: u32) -> Vec<(NaiveDate, f64)> {
+    let window = window.max(1);
+    let mut entries = entries.to_vec();
+    entries.sort_by_key(|entry| entry.date);
+    entries
+        .iter()
+        .map(|entry| {
+            let since = entry.date - Duration::days(i64::from(window) - 1);
+            let total: f64 = entries
+                .iter()
+                .filter(|other| other.date >= since && other.date <= entry.date)
+                .map(|other| other.active)
+                .sum();
+            (entry.date, total / f64::from(window))
+        })
+        .collect()
+}
@@ -1,3 +1,6 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 
 use crate::Location;
@@ -39,7 +42,8 @@ pub struct UserDataGroup {
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserDataGPA {
-    pub gpa: String,
+    #[serde(with = "crate::serde_utils::number")]
+    pub gpa: f32,
     pub cycle: String,
 }
 
@@ -77,8 +81,10 @@ pub struct UserData {
     pub userdocs: Option<String>,
     pub shell: Option<String>,
     pub close: bool,
-    pub ctime: String,
-    pub mtime: String,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub ctime: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub mtime: NaiveDateTime,
     pub id_promo: Option<String>,
     pub id_history: Option<String>,
     pub course_code: Option<String>,
@@ -101,8 +107,16 @@ pub struct UserData {
     pub nsstat: Option<UserDataNsStat>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct UserNetsoulEntry(u64, f64, f64, f64, f64, f64);
+/// The log time of a student for one day, with durations expressed in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct UserNetsoulEntry {
+    pub date: NaiveDate,
+    pub active: f64,
+    pub idle: f64,
+    pub out_active: f64,
+    pub out_idle: f64,
+    pub average: f64,
+}
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserNotes {
@@ -117,7 +131,8 @@ pub struct UserNotesModule {
     pub codemodule: Option<String>,
     pub codeinstance: Option<String>,
     pub title: Option<String>,
-    pub date_ins: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub date_ins: Option<NaiveDateTime>,
     pub cycle: Option<String>,
     pub grade: Option<String>,
     pub credits: Option<f32>,
@@ -132,7 +147,8 @@ pub struct UserNotesMark {
     pub codeinstance: String,
     pub codeacti: String,
     pub title: String,
-    pub date: String,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub date: NaiveDateTime,
     pub correcteur: String,
     pub final_note: f32,
     pub comment: Option<String>,
@@ -156,8 +172,10 @@ pub struct UserBinomeEntry {
     pub picture: String,
     pub activities: String,
     pub id_activities: String,
-    pub nb_activities: String,
-    pub weight: String,
+    #[serde(with = "crate::serde_utils::number")]
+    pub nb_activities: u32,
+    #[serde(with = "crate::serde_utils::number")]
+    pub weight: f32,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -173,9 +191,19 @@ pub struct UserSearchResultEntry {
     pub course: Option<String>,
 }
 
+/// A campus along with its number of students, as listed in `/user/filter/location`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocationEntry {
+    pub code: Location,
+    pub title: String,
+    #[serde(with = "crate::serde_utils::number")]
+    pub students: u32,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AvailableCourseEntry {
-    pub students: String,
+    #[serde(with = "crate::serde_utils::number")]
+    pub students: u32,
     pub code: String,
     pub shortcode_school: String,
     pub title: String,
@@ -184,7 +212,515 @@ pub struct AvailableCourseEntry {
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AvailablePromoEntry {
-    pub students: String,
+    #[serde(with = "crate::serde_utils::number")]
+    pub students: u32,
     pub promo: String,
     pub promo_deprecated: String,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventRegistration {
+    Unregistered,
+    Registered,
+    Present,
+    Absent,
+}
+
+impl Default for EventRegistration {
+    #[inline]
+    fn default() -> EventRegistration {
+        EventRegistration::Unregistered
+    }
+}
+
+impl Serialize for EventRegistration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            EventRegistration::Unregistered => serializer.serialize_bool(false),
+            EventRegistration::Registered => serializer.serialize_str("registered"),
+            EventRegistration::Present => serializer.serialize_str("present"),
+            EventRegistration::Absent => serializer.serialize_str("absent"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EventRegistration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Flag(bool),
+            State(String),
+        }
+
+        match Option::<Repr>::deserialize(deserializer)? {
+            None | Some(Repr::Flag(false)) => Ok(EventRegistration::Unregistered),
+            Some(Repr::Flag(true)) => Ok(EventRegistration::Registered),
+            Some(Repr::State(state)) => match state.as_str() {
+                "" => Ok(EventRegistration::Unregistered),
+                "registered" => Ok(EventRegistration::Registered),
+                "present" => Ok(EventRegistration::Present),
+                "absent" => Ok(EventRegistration::Absent),
+                _ => Err(de::Error::unknown_variant(
+                    state.as_str(),
+                    &["registered", "present", "absent"],
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanningRoom {
+    pub code: Option<String>,
+    #[serde(rename = "type")]
+    pub room_type: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub seats: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanningEvent {
+    #[serde(with = "crate::serde_utils::number")]
+    pub scolaryear: u32,
+    pub codemodule: String,
+    pub codeinstance: String,
+    pub codeacti: String,
+    pub codeevent: String,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub semester: Option<u32>,
+    pub instance_location: Option<String>,
+    pub titlemodule: Option<String>,
+    pub acti_title: Option<String>,
+    pub title: Option<String>,
+    pub type_title: Option<String>,
+    pub type_code: Option<String>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub start: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub end: NaiveDateTime,
+    pub room: Option<PlanningRoom>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub total_students_registered: Option<u32>,
+    #[serde(default)]
+    pub module_available: bool,
+    #[serde(default)]
+    pub module_registered: bool,
+    #[serde(default)]
+    pub allow_register: bool,
+    #[serde(default)]
+    pub allow_token: bool,
+    #[serde(default)]
+    pub project: bool,
+    #[serde(default)]
+    pub past: bool,
+    #[serde(default)]
+    pub event_registered: EventRegistration,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModuleStaff {
+    #[serde(rename = "type")]
+    pub staff_type: Option<String>,
+    pub login: String,
+    pub title: String,
+    pub picture: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleDetail {
+    #[serde(with = "crate::serde_utils::number")]
+    pub scolaryear: u32,
+    pub codemodule: String,
+    pub codeinstance: String,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub semester: Option<u32>,
+    pub title: String,
+    pub description: Option<String>,
+    pub competence: Option<String>,
+    pub instance_location: Option<String>,
+    #[serde(with = "crate::serde_utils::date")]
+    pub begin: NaiveDate,
+    #[serde(with = "crate::serde_utils::date")]
+    pub end: NaiveDate,
+    #[serde(default, with = "crate::serde_utils::option_date")]
+    pub end_register: Option<NaiveDate>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub credits: Option<u32>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub max_ins: Option<u32>,
+    #[serde(default)]
+    pub resp: Vec<ModuleStaff>,
+    #[serde(default)]
+    pub assistant: Vec<ModuleStaff>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub past: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub closed: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub opened: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub allow_register: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub student_registered: bool,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub date_ins: Option<NaiveDateTime>,
+    pub student_grade: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub student_credits: Option<u32>,
+    #[serde(default, rename = "activites")]
+    pub activities: Vec<ModuleActivity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleActivity {
+    pub codeacti: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub type_title: Option<String>,
+    pub type_code: Option<String>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub begin: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub end: NaiveDateTime,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub end_register: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub deadline: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub is_projet: bool,
+    pub project_title: Option<String>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub is_note: bool,
+    #[serde(default)]
+    pub events: Vec<ActivityEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityDetail {
+    #[serde(with = "crate::serde_utils::number")]
+    pub scolaryear: u32,
+    pub codemodule: String,
+    pub codeinstance: String,
+    pub codeacti: String,
+    pub module_title: Option<String>,
+    pub title: String,
+    pub description: Option<String>,
+    pub type_title: Option<String>,
+    pub type_code: Option<String>,
+    pub instance_location: Option<String>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub begin: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub end: NaiveDateTime,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub end_register: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub deadline: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub nb_group: Option<u32>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub register: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub is_projet: bool,
+    pub id_projet: Option<String>,
+    pub project_title: Option<String>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub is_note: bool,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub nb_notes: Option<u32>,
+    pub rdv_status: Option<String>,
+    #[serde(default)]
+    pub events: Vec<ActivityEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    pub code: String,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub num_event: Option<u32>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub seats: Option<u32>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub nb_inscrits: Option<u32>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub begin: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub end: NaiveDateTime,
+    pub location: Option<String>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub already_register: bool,
+    pub user_status: Option<String>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub allow_token: bool,
+    #[serde(default)]
+    pub resp: Vec<ModuleStaff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TokenValidation {
+    Accepted,
+    WrongToken,
+    AlreadyValidated,
+    WindowClosed,
+}
+
+impl TokenValidation {
+    /// Interprets the error reply of the intranet to a token submission.
+    pub(crate) fn from_refusal(body: &str) -> Option<TokenValidation> {
+        let message = crate::error::intranet_message(body).to_lowercase();
+        let matches = |phrases: &[&str]| {
+            phrases
+                .iter()
+                .any(|phrase| crate::error::contains_phrase(&message, phrase))
+        };
+        if matches(&[
+            "already validated",
+            "already been validated",
+            "déjà validé",
+            "déjà été validé",
+        ]) {
+            Some(TokenValidation::AlreadyValidated)
+        } else if matches(&[
+            "invalid token",
+            "wrong token",
+            "incorrect token",
+            "token is invalid",
+            "token invalide",
+            "token incorrect",
+            "token est invalide",
+            "token est incorrect",
+            "mauvais token",
+        ]) {
+            Some(TokenValidation::WrongToken)
+        } else if matches(&[
+            "closed",
+            "is over",
+            "expired",
+            "fermé",
+            "fermée",
+            "terminé",
+            "terminée",
+        ]) {
+            Some(TokenValidation::WindowClosed)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationUser {
+    pub title: String,
+    pub url: Option<String>,
+    pub picture: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationMessage {
+    pub id: String,
+    #[serde(with = "crate::serde_utils::html")]
+    pub title: String,
+    #[serde(default, with = "crate::serde_utils::option_html")]
+    pub content: Option<String>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub date: NaiveDateTime,
+    pub user: Option<NotificationUser>,
+    pub class: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationAlert {
+    pub id: String,
+    #[serde(with = "crate::serde_utils::html")]
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationEvent {
+    #[serde(with = "crate::serde_utils::html")]
+    pub acti_title: String,
+    pub categ_title: Option<String>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub begin: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub end: NaiveDateTime,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub scolaryear: Option<u32>,
+    pub codemodule: Option<String>,
+    pub codeinstance: Option<String>,
+    pub codeacti: Option<String>,
+    pub codeevent: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_html")]
+    pub module_title: Option<String>,
+    pub link_module: Option<String>,
+    pub link_event: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectMember {
+    pub login: String,
+    pub title: Option<String>,
+    pub picture: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectGroup {
+    pub id: Option<String>,
+    pub title: String,
+    pub code: String,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub final_note: Option<f32>,
+    pub repository: Option<String>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub closed: bool,
+    pub master: ProjectMember,
+    #[serde(default)]
+    pub members: Vec<ProjectMember>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDetail {
+    #[serde(with = "crate::serde_utils::number")]
+    pub scolaryear: u32,
+    pub codemodule: String,
+    pub codeinstance: String,
+    pub codeacti: String,
+    pub module_title: Option<String>,
+    pub project_title: Option<String>,
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub begin: NaiveDateTime,
+    #[serde(with = "crate::serde_utils::datetime")]
+    pub end: NaiveDateTime,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub end_register: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub deadline: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub nb_min: Option<u32>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub nb_max: Option<u32>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub register: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub closed: bool,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub instance_registered: bool,
+    pub user_project_status: Option<String>,
+    pub user_project_code: Option<String>,
+    pub user_project_title: Option<String>,
+    #[serde(default, with = "crate::serde_utils::flag")]
+    pub user_project_master: bool,
+    #[serde(default)]
+    pub registered: Vec<ProjectGroup>,
+    #[serde(default)]
+    pub notregistered: Vec<ProjectMember>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectFile {
+    pub title: String,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub slug: Option<String>,
+    pub mime: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub size: Option<u64>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub ctime: Option<NaiveDateTime>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub mtime: Option<NaiveDateTime>,
+    pub fullpath: String,
+}
+
+impl ProjectFile {
+    /// The absolute URL from which the file can be downloaded from the intranet at `endpoint`
+    /// (eg. `client.endpoint()`).
+    pub fn download_url(&self, endpoint: &str) -> String {
+        format!("{}{}", endpoint.trim_end_matches('/'), self.fullpath)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectMark {
+    pub login: String,
+    pub title: Option<String>,
+    pub picture: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_number")]
+    pub note: Option<f32>,
+    pub grader: Option<String>,
+    pub comment: Option<String>,
+    #[serde(default, with = "crate::serde_utils::option_datetime")]
+    pub date: Option<NaiveDateTime>,
+}
+
+/// A mark of a student along with the marks of every group of the same activity.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkDetail {
+    pub mark: UserNotesMark,
+    pub marks: Vec<ProjectMark>,
+}
+
+impl MarkDetail {
+    /// The corrections received by a given student for this activity, in chronological order.
+    pub fn corrections_of(&self, login: &str) -> Vec<&ProjectMark> {
+        let mut corrections: Vec<_> = self
+            .marks
+            .iter()
+            .filter(|mark| mark.login == login)
+            .collect();
+        corrections.sort_by_key(|mark| mark.date);
+        corrections
+    }
+}
+
+/// The autologin link of the authenticated student, as listed in `/admin/autolog`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AutologinLink {
+    pub autologin: String,
+}
+
+impl Serialize for UserNetsoulEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let timestamp = self
+            .date
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp();
+        let repr = (
+            timestamp,
+            self.active,
+            self.idle,
+            self.out_active,
+            self.out_idle,
+            self.average,
+        );
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UserNetsoulEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (timestamp, active, idle, out_active, out_idle, average) =
+            <(i64, f64, f64, f64, f64, f64)>::deserialize(deserializer)?;
+        // The timestamps are midnight in the campus' timezone, shifting them to noon
+        // makes the UTC date match the local one.
+        let date = chrono::DateTime::from_timestamp(timestamp + 12 * 3600, 0)
+            .ok_or_else(|| de::Error::custom(format!("invalid timestamp: {}", timestamp)))?
+            .date_naive();
+        Ok(UserNetsoulEntry {
+            date,
+            active,
+            idle,
+            out_active,
+            out_idle,
+            average,
+        })
+    }
+}
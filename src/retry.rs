@@ -0,0 +1,147 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::error::Error;
+
+/// Controls how failed requests get retried by `Client::make_request`.
+///
+/// Between two attempts, the client waits for an exponentially growing delay
+/// (`base_delay * 2^n`, capped to `max_delay`), optionally randomized to avoid
+/// synchronized retries from multiple clients.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    respect_retry_after: bool,
+    predicate: Arc<dyn Fn(&Error) -> bool + Send + Sync>,
+}
+
+impl RetryPolicy {
+    pub fn new() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            respect_retry_after: true,
+            predicate: Arc::new(Error::is_transient),
+        }
+    }
+
+    /// A policy that sends every request only once.
+    pub fn never() -> RetryPolicy {
+        RetryPolicy::new().max_attempts(1)
+    }
+
+    /// The maximum number of times a request is sent (at least one).
+    #[inline]
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    #[inline]
+    pub fn base_delay(mut self, base_delay: Duration) -> RetryPolicy {
+        self.base_delay = base_delay;
+        self
+    }
+
+    #[inline]
+    pub fn max_delay(mut self, max_delay: Duration) -> RetryPolicy {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Whether to randomize each delay between half and the full computed delay.
+    #[inline]
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Whether to wait for the delay requested by the intranet through the `Retry-After` header,
+    /// when present (still capped to `max_delay`).
+    #[inline]
+    pub fn respect_retry_after(mut self, respect_retry_after: bool) -> RetryPolicy {
+        self.respect_retry_after = respect_retry_after;
+        self
+    }
+
+    /// Sets which errors are worth retrying (defaults to `Error::is_transient`).
+    #[inline]
+    pub fn retry_if<F>(mut self, predicate: F) -> RetryPolicy
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Arc::new(predicate);
+        self
+    }
+
+    #[inline]
+    pub(crate) fn attempts(&self) -> u32 {
+        self.max_attempts.max(1)
+    }
+
+    #[inline]
+    pub(crate) fn should_retry(&self, error: &Error) -> bool {
+        (self.predicate)(error)
+    }
+
+    /// Computes the delay to wait for before the retry following the failed attempt `attempt` (zero-based).
+    pub(crate) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let exponent = attempt.min(31);
+        let delay = self
+            .base_delay
+            .checked_mul(1 << exponent)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let delay = if self.jitter && delay > Duration::from_millis(0) {
+            let half = delay / 2;
+            let nanos = rand::thread_rng().gen_range(0, half.as_nanos() as u64 + 1);
+            half + Duration::from_nanos(nanos)
+        } else {
+            delay
+        };
+        match retry_after {
+            Some(retry_after) if self.respect_retry_after => {
+                delay.max(retry_after).min(self.max_delay)
+            }
+            _ => delay,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    #[inline]
+    fn default() -> RetryPolicy {
+        RetryPolicy::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("respect_retry_after", &self.respect_retry_after)
+            .finish()
+    }
+}
+
+/// Parses the value of a `Retry-After` header, either as a number of seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.timestamp() - chrono::Utc::now().timestamp();
+    Some(Duration::from_secs(delay.max(0) as u64))
+}
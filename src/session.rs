@@ -0,0 +1,105 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// The authentication state of a `Client`, which can be persisted to avoid re-authenticating on every run.
+///
+/// Sessions are (de)serialized as JSON, through `Display`/`FromStr` or `save`/`load`.
+/// They hold the session cookie, so they should be stored as carefully as an autologin link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// The login of the authenticated student.
+    pub login: String,
+    /// The value of the `user` cookie.
+    pub cookie: String,
+    /// The autologin link to use to re-authenticate once the cookie expires.
+    pub autologin: Option<String>,
+    /// The expiration date of the cookie, if announced by the intranet.
+    pub expires: Option<SystemTime>,
+}
+
+impl Session {
+    /// Whether the cookie is known to be expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| expires <= SystemTime::now())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_string()).map_err(|err| Error::IoError(err.to_string()))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Session, Error> {
+        let contents = fs::read_to_string(path).map_err(|err| Error::IoError(err.to_string()))?;
+        contents.parse()
+    }
+
+    /// The value of the `Cookie` header to send along with requests.
+    pub(crate) fn cookie_header(&self) -> Result<header::HeaderValue, Error> {
+        header::HeaderValue::from_str(&format!("user={}", self.cookie))
+            .map_err(|_| Error::CookieNotFound)
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let repr = json::to_string(self).map_err(|_| fmt::Error)?;
+        write!(f, "{}", repr)
+    }
+}
+
+impl FromStr for Session {
+    type Err = Error;
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let session = json::from_str(string)?;
+        Ok(session)
+    }
+}
+
+/// Extracts the `user` cookie (and its expiration date) out of the `Set-Cookie` headers of an intranet response.
+pub(crate) fn extract_cookie<'a, I>(set_cookies: I) -> Result<(String, Option<SystemTime>), Error>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let set_cookie = set_cookies
+        .into_iter()
+        .find(|cookie| cookie.starts_with("user="))
+        .ok_or(Error::CookieNotFound)?;
+    let mut attributes = set_cookie.split(';').map(str::trim);
+    let cookie = attributes
+        .next()
+        .and_then(|cookie| cookie.strip_prefix("user="))
+        .filter(|cookie| !cookie.is_empty() && *cookie != "deleted")
+        .ok_or(Error::CookieNotFound)?
+        .to_string();
+    let mut expires = None;
+    for attribute in attributes {
+        let mut parts = attribute.splitn(2, '=');
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let value = parts.next().unwrap_or_default();
+        match name.as_str() {
+            "max-age" => {
+                if let Ok(seconds) = value.parse::<u64>() {
+                    expires = Some(SystemTime::now() + std::time::Duration::from_secs(seconds));
+                    break;
+                }
+            }
+            "expires" => {
+                // Cookie dates are sometimes written with dashes (eg. `Wed, 21-Oct-2020 07:28:00 GMT`).
+                let value = value.replace('-', " ");
+                if let Ok(date) = chrono::DateTime::parse_from_rfc2822(&value) {
+                    expires = Some(SystemTime::from(date));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((cookie, expires))
+}
@@ -1,30 +1,1187 @@
-use enum_iterator::IntoEnumIterator;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use chrono::NaiveDate;
 use futures::future;
 use futures::future::FutureExt;
-use serde::{Deserialize, Serialize};
+use futures::stream::{StreamExt, TryStreamExt};
+use reqwest::header;
 
-use crate::{Client, Error, Location, Promo};
+use crate::auth::{Authenticator, CookieAuthenticator, CookieJar, Office365Authenticator};
+use crate::cache::{Cache, CacheEntry, CachePolicy, DiskCache, MemoryCache};
+use crate::error::{ParseLocationError, ParsePromoError};
+use crate::limit::Limiter;
+use crate::mock::MockIntranet;
+use crate::retry::{self, RetryPolicy};
+use crate::session::{self, Session};
+use crate::transport::{self, FnTransport, Middleware, ReqwestTransport, Transport};
+use crate::vcr::{Cassette, Recorder, Replayer};
+use crate::{netsoul, response, Client, Error, EventRef, Location, Promo, ENDPOINT};
 
+/// Authenticates against the live intranet if `EPITECH_AUTOLOGIN` is set at build time,
+/// or against a local mock otherwise.
 async fn setup_client() -> Result<Client, Error> {
-    Client::builder()
-        .autologin(env!("EPITECH_AUTOLOGIN"))
-        .authenticate()
-        .await
+    match option_env!("EPITECH_AUTOLOGIN") {
+        Some(autologin) => Client::builder().autologin(autologin).authenticate().await,
+        None => MockIntranet::start().client().authenticate().await,
+    }
 }
 
 #[tokio::test]
 async fn auth_unreachable_remote() {
-    let client = Client::builder().autologin("toto").authenticate().await;
-    assert!(client.is_err());
+    let client = Client::builder()
+        .autologin("http://127.0.0.1:1/auth-0123456789")
+        .authenticate()
+        .await;
     assert_eq!(client.unwrap_err(), Error::UnreachableRemote);
 }
 
+#[tokio::test]
+async fn auth_invalid_autologin() {
+    let client = Client::builder().autologin("toto").authenticate().await;
+    let err = client.unwrap_err();
+    assert!(matches!(err, Error::InvalidRequest(_)), "{:?}", err);
+    assert!(!err.is_transient());
+}
+
+#[test]
+fn retry_policy_delays() {
+    let policy = RetryPolicy::new()
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_secs(1))
+        .jitter(false);
+    assert_eq!(policy.delay(0, None), Duration::from_millis(100));
+    assert_eq!(policy.delay(2, None), Duration::from_millis(400));
+    assert_eq!(policy.delay(10, None), Duration::from_secs(1));
+    assert_eq!(
+        policy.delay(0, retry::parse_retry_after("1")),
+        Duration::from_secs(1)
+    );
+    let jittered = policy.jitter(true).delay(1, None);
+    assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn retry_limit() {
+    let intranet = MockIntranet::start();
+    let client = intranet
+        .client()
+        .retry_policy(
+            RetryPolicy::new()
+                .max_attempts(3)
+                .base_delay(Duration::from_millis(1))
+                .jitter(false),
+        )
+        .authenticate()
+        .await
+        .unwrap();
+    intranet.refuse("/course/filter", 503, "Service Unavailable");
+    assert_eq!(
+        client.make_request("/course/filter").await,
+        Err(Error::RetryLimit(Box::new(Error::ServerError(503))))
+    );
+    assert_eq!(intranet.requests("/course/filter"), 3);
+
+    // Errors that are not transient are returned right away.
+    intranet.refuse("/course/filter", 403, "Forbidden");
+    assert_eq!(
+        client.make_request("/course/filter").await,
+        Err(Error::Forbidden)
+    );
+    assert_eq!(intranet.requests("/course/filter"), 4);
+}
+
+#[tokio::test]
+async fn server_errors() {
+    let intranet = MockIntranet::start();
+    let client = intranet
+        .client()
+        .retry_policy(RetryPolicy::never())
+        .authenticate()
+        .await
+        .unwrap();
+    intranet.refuse("/course/filter", 502, "<h1>Bad Gateway</h1>");
+    assert_eq!(
+        client.make_request("/course/filter").await,
+        Err(Error::ServerError(502))
+    );
+    assert_eq!(intranet.requests("/course/filter"), 1);
+}
+
+#[tokio::test]
+async fn limiter_throttles_requests() {
+    // The clock is paused, so delays complete as soon as the runtime is idle.
+    tokio::time::pause();
+    let limiter = Limiter::new(Some((2, Duration::from_millis(200))), None);
+    let start = tokio::time::Instant::now();
+    for _ in 0..2 {
+        assert!(limiter.acquire().now_or_never().is_some());
+    }
+    limiter.acquire().await;
+    limiter.acquire().await;
+    // The first two requests are sent right away, the next ones once every 100ms.
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(199), "{:?}", elapsed);
+    assert!(elapsed <= Duration::from_millis(210), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn limiter_bounds_requests_in_flight() {
+    tokio::time::pause();
+    let limiter = Limiter::new(None, Some(2));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let requests = (0..6).map(|_| {
+        let (limiter, in_flight, peak) = (limiter.clone(), in_flight.clone(), peak.clone());
+        async move {
+            let _permit = limiter.acquire().await;
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(current, Ordering::SeqCst);
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        }
+    });
+    future::join_all(requests).await;
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn download_holds_permit() {
+    let intranet = MockIntranet::start();
+    let client = intranet
+        .client()
+        .max_in_flight(1)
+        .authenticate()
+        .await
+        .unwrap();
+    let download = client
+        .download("/module/2019/B-CPE-110/STG-1-1/acti-350001/project/file/bsq.pdf")
+        .await
+        .unwrap();
+    // The body is still to be read, so the download keeps counting as in flight.
+    assert!(client.limiter.acquire().now_or_never().is_none());
+    drop(download);
+    assert!(client.limiter.acquire().now_or_never().is_some());
+}
+
+#[test]
+fn memory_cache_evicts_least_recently_used() {
+    let entry = |body: &str| CacheEntry {
+        body: String::from(body),
+        etag: None,
+        last_modified: None,
+        stored_at: SystemTime::now(),
+    };
+    let cache = MemoryCache::new(2);
+    cache.put("a", entry("a"));
+    cache.put("b", entry("b"));
+    assert!(cache.get("a").is_some());
+    cache.put("c", entry("c"));
+    assert!(cache.get("b").is_none());
+    assert_eq!(
+        cache.get("a").map(|entry| entry.body),
+        Some(String::from("a"))
+    );
+    assert_eq!(
+        cache.get("c").map(|entry| entry.body),
+        Some(String::from("c"))
+    );
+}
+
+#[test]
+fn disk_cache_roundtrip() {
+    let directory = std::env::temp_dir().join(format!("epitech-api-cache-{}", std::process::id()));
+    let cache = DiskCache::new(&directory).unwrap();
+    let entry = CacheEntry {
+        body: String::from("{}"),
+        etag: Some(String::from("\"abc\"")),
+        last_modified: None,
+        stored_at: SystemTime::now(),
+    };
+    cache.put("login|/user", entry.clone());
+    assert_eq!(cache.get("login|/user"), Some(entry));
+    assert_eq!(cache.get("other|/user"), None);
+    cache.remove("login|/user");
+    assert_eq!(cache.get("login|/user"), None);
+    let _ = std::fs::remove_dir_all(directory);
+}
+
+#[test]
+fn cache_policy_ttls() {
+    let policy = CachePolicy::new(Duration::from_secs(60))
+        .ttl("/user/filter", Duration::from_secs(3600))
+        .ttl("/user/filter/course", Duration::from_secs(86400));
+    assert_eq!(policy.ttl_for("/user"), Duration::from_secs(60));
+    assert_eq!(
+        policy.ttl_for("/user/filter/user?offset=0"),
+        Duration::from_secs(3600)
+    );
+    assert_eq!(
+        policy.ttl_for("/user/filter/course?format=json"),
+        Duration::from_secs(86400)
+    );
+    assert_eq!(
+        policy.ttl_for("https://intra.epitech.eu/user/filter/user?format=json"),
+        Duration::from_secs(3600)
+    );
+    assert_eq!(
+        policy.ttl_for("https://intra.epitech.eu/user"),
+        Duration::from_secs(60)
+    );
+    assert_eq!(
+        policy.ttl_for("https://intra.epitech.eu"),
+        Duration::from_secs(60)
+    );
+}
+
+/// Records the status of every response.
+#[derive(Debug, Default)]
+struct StatusLog {
+    statuses: Arc<Mutex<Vec<u16>>>,
+}
+
+#[async_trait::async_trait]
+impl Middleware for StatusLog {
+    async fn handle(
+        &self,
+        request: transport::Request,
+        next: &dyn Transport,
+    ) -> Result<transport::Response, Error> {
+        let response = next.send(request).await?;
+        self.statuses.lock().unwrap().push(response.status);
+        Ok(response)
+    }
+}
+
+#[tokio::test]
+async fn cached_requests() {
+    let intranet = MockIntranet::start();
+    let log = StatusLog::default();
+    let statuses = log.statuses.clone();
+    let policy = CachePolicy::new(Duration::from_secs(0)).ttl("/user", Duration::from_secs(3600));
+    let client = intranet
+        .client()
+        .cache(MemoryCache::new(16))
+        .cache_policy(policy)
+        .middleware(log)
+        .authenticate()
+        .await
+        .unwrap();
+    let requests = intranet.requests("/user");
+    statuses.lock().unwrap().clear();
+
+    // Hits, including through the full URL of the route.
+    let body = client.make_request("/user").await.unwrap();
+    assert_eq!(client.make_request("/user").await.unwrap(), body);
+    let url = format!("{}/user", intranet.url());
+    assert_eq!(client.make_request(url).await.unwrap(), body);
+    assert_eq!(intranet.requests("/user"), requests + 1);
+
+    // Bypasses skip the lookup, but refresh the entry.
+    let requests = intranet.requests("/user");
+    assert_eq!(
+        client.bypass_cache().make_request("/user").await.unwrap(),
+        body
+    );
+    assert_eq!(intranet.requests("/user"), requests + 1);
+    assert_eq!(statuses.lock().unwrap().last(), Some(&200));
+
+    // Stale entries are revalidated, and the body is not sent again.
+    statuses.lock().unwrap().clear();
+    let planning = client.make_request("/planning/load").await.unwrap();
+    assert_eq!(
+        client.make_request("/planning/load").await.unwrap(),
+        planning
+    );
+    assert_eq!(
+        client.make_request("/planning/load").await.unwrap(),
+        planning
+    );
+    assert_eq!(*statuses.lock().unwrap(), [200, 304, 304]);
+    assert_eq!(intranet.requests("/planning/load"), 3);
+
+    // Registrations evict the module and the activity they change, but not the other entries.
+    let client = intranet
+        .client()
+        .cache(MemoryCache::new(16))
+        .cache_policy(CachePolicy::new(Duration::from_secs(3600)))
+        .authenticate()
+        .await
+        .unwrap();
+    let module = "/module/2019/B-PRO-300/STG-3-1";
+    let activity = "/module/2019/B-PRO-300/STG-3-1/acti-360401";
+    client
+        .fetch_module(2019, "B-PRO-300", "STG-3-1")
+        .await
+        .unwrap();
+    client
+        .fetch_activity(2019, "B-PRO-300", "STG-3-1", "acti-360401")
+        .await
+        .unwrap();
+    client.make_request("/user").await.unwrap();
+    let requests = |route| intranet.requests(route);
+    let before = (requests(module), requests(activity), requests("/user"));
+    client
+        .register_module(2019, "B-PRO-300", "STG-3-1")
+        .await
+        .unwrap();
+    client
+        .fetch_module(2019, "B-PRO-300", "STG-3-1")
+        .await
+        .unwrap();
+    client
+        .fetch_activity(2019, "B-PRO-300", "STG-3-1", "acti-360401")
+        .await
+        .unwrap();
+    client.make_request("/user").await.unwrap();
+    assert_eq!(requests(module), before.0 + 1);
+    assert_eq!(requests(activity), before.1);
+    assert_eq!(requests("/user"), before.2);
+
+    let event = EventRef {
+        scolaryear: 2019,
+        codemodule: String::from("B-PRO-300"),
+        codeinstance: String::from("STG-3-1"),
+        codeacti: String::from("acti-360401"),
+        codeevent: String::from("event-123456"),
+    };
+    client.register_event(&event).await.unwrap();
+    client
+        .fetch_module(2019, "B-PRO-300", "STG-3-1")
+        .await
+        .unwrap();
+    client
+        .fetch_activity(2019, "B-PRO-300", "STG-3-1", "acti-360401")
+        .await
+        .unwrap();
+    assert_eq!(requests(module), before.0 + 2);
+    assert_eq!(requests(activity), before.1 + 1);
+}
+
+#[test]
+fn session_persistence() {
+    let mut headers = header::HeaderMap::new();
+    headers.append(
+        header::SET_COOKIE,
+        header::HeaderValue::from_static("language=fr; path=/"),
+    );
+    headers.append(
+        header::SET_COOKIE,
+        header::HeaderValue::from_static(
+            "user=abc123; expires=Wed, 21-Oct-2037 07:28:00 GMT; path=/",
+        ),
+    );
+    let set_cookies = headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok());
+    let (cookie, expires) = session::extract_cookie(set_cookies).unwrap();
+    assert_eq!(cookie, "abc123");
+    assert!(expires.is_some());
+    let session = Session {
+        login: String::from("first.last@epitech.eu"),
+        cookie,
+        autologin: Some(String::from("https://intra.epitech.eu/auth-0123456789")),
+        expires,
+    };
+    assert!(!session.is_expired());
+    let restored = Client::builder()
+        .restore(session.to_string().parse().unwrap())
+        .unwrap();
+    assert_eq!(restored.session(), session);
+}
+
+/// Serves a mock of the Office365 redirect chain of the intranet, returning its base URL.
+///
+/// Its login form rejects the submissions lacking the hidden fields of the page.
+fn spawn_office365_mock() -> String {
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Method, Request, Response, Server, StatusCode};
+
+    const LOGIN_PAGE: &str = r#"<html><body>
+        <FORM name="f1" method="post" action="/common/login?sso_reload=true">
+            <input type="hidden" name="flowToken" value="flow42">
+            <input type='hidden' name='ctx' value='rQ&amp;1'/>
+            <input type=hidden name=canary value=c/a==>
+            <input type="email" name="login" value="">
+            <input type="password" name="passwd">
+        </FORM>
+    </body></html>"#;
+
+    async fn handle(request: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        let cookies = request
+            .headers()
+            .get(header::COOKIE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let redirect = |location: &str| {
+            Response::builder()
+                .status(StatusCode::FOUND)
+                .header(header::LOCATION, location)
+        };
+        let response = match (request.method(), request.uri().path()) {
+            (&Method::GET, "/auth/office365") => redirect("/oauth2/authorize?client_id=intra")
+                .header(header::SET_COOKIE, "esctx=state42; path=/")
+                .body(Body::empty()),
+            (&Method::GET, "/oauth2/authorize") if cookies.contains("esctx=state42") => {
+                Response::builder()
+                    .header(header::SET_COOKIE, "buid=b1; Path=/common; HttpOnly")
+                    .body(Body::from(LOGIN_PAGE))
+            }
+            (&Method::POST, "/common/login")
+                if cookies.contains("esctx=state42") && cookies.contains("buid=b1") =>
+            {
+                let form = hyper::body::to_bytes(request.into_body()).await?;
+                let form = String::from_utf8_lossy(&form);
+                let fields: Vec<_> = form.split('&').collect();
+                let hidden = ["flowToken=flow42", "ctx=rQ%261", "canary=c%2Fa%3D%3D"];
+                if !hidden.iter().all(|field| fields.contains(field)) {
+                    Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::empty())
+                } else if fields.contains(&"login=first.last%40epitech.eu")
+                    && fields.contains(&"passwd=hunter2")
+                {
+                    redirect("/auth/office365/callback?code=1234").body(Body::empty())
+                } else {
+                    Response::builder().body(Body::from(LOGIN_PAGE))
+                }
+            }
+            (&Method::GET, "/auth/office365/callback") => redirect("/")
+                .header(
+                    header::SET_COOKIE,
+                    "user=office365-cookie; Max-Age=3600; path=/",
+                )
+                .body(Body::empty()),
+            _ => Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::empty()),
+        };
+        Ok(response.unwrap())
+    }
+
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service_fn(|_| async {
+        Ok::<_, hyper::Error>(service_fn(handle))
+    }));
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    url
+}
+
+#[tokio::test]
+async fn office365_authentication() {
+    let endpoint = spawn_office365_mock();
+    let authenticator = Office365Authenticator::new("first.last@epitech.eu", "hunter2");
+    let transport = ReqwestTransport::new().unwrap();
+    let credentials = authenticator
+        .authenticate(&transport, &endpoint)
+        .await
+        .unwrap();
+    assert_eq!(credentials.cookie, "office365-cookie");
+    assert!(credentials.expires.is_some());
+
+    let authenticator = Office365Authenticator::new("first.last@epitech.eu", "wrong");
+    assert_eq!(
+        authenticator.authenticate(&transport, &endpoint).await,
+        Err(Error::CookieNotFound)
+    );
+}
+
+#[tokio::test]
+async fn office365_foreign_session_cookies() {
+    // Session cookies set by the login pages are ignored, only the intranet hands out the real one.
+    let transport = FnTransport::new(|request| {
+        let response = match (request.method.as_str(), request.url.as_str()) {
+            ("GET", "https://intra.test/auth/office365") => transport::Response::new(302, "")
+                .header("Location", "https://login.test/oauth2/authorize"),
+            ("GET", "https://login.test/oauth2/authorize") => transport::Response::new(
+                200,
+                r#"<form method="post" action="/common/login"></form>"#,
+            )
+            .header("Set-Cookie", "user=forged; path=/"),
+            ("POST", "https://login.test/common/login") => transport::Response::new(302, "")
+                .header("Location", "https://intra.test/auth/office365/callback")
+                .header("Set-Cookie", "user=forged-again; path=/"),
+            ("GET", "https://intra.test/auth/office365/callback") => {
+                assert_eq!(request.header_value("cookie"), None);
+                transport::Response::new(302, "")
+                    .header("Location", "/")
+                    .header("Set-Cookie", "user=intra-cookie; path=/")
+            }
+            ("GET", "https://intra.test/user?format=json") => {
+                let body = include_str!("../fixtures/user.json");
+                transport::Response::new(200, body.replace("{login}", "first.last@epitech.eu"))
+            }
+            _ => transport::Response::new(404, ""),
+        };
+        Ok(response)
+    });
+    // The flow starts at the endpoint of the client.
+    let client = Client::builder()
+        .endpoint("https://intra.test")
+        .transport(transport)
+        .authenticator(Office365Authenticator::new(
+            "first.last@epitech.eu",
+            "hunter2",
+        ))
+        .authenticate()
+        .await
+        .unwrap();
+    assert_eq!(client.session().cookie, "intra-cookie");
+}
+
+#[test]
+fn cookie_jar() {
+    let url = |url: &str| reqwest::Url::parse(url).unwrap();
+    let mut jar = CookieJar::default();
+    jar.store(
+        &url("https://login.microsoftonline.com/common/oauth2/authorize"),
+        vec![
+            "esctx=state42; domain=.microsoftonline.com; path=/; secure",
+            "buid=b1; Path=/common",
+            "fpc=f1",
+            "stolen=1; Domain=epitech.eu",
+        ],
+    );
+    let header = |jar: &CookieJar, address: &str| jar.header(&url(address));
+    assert_eq!(
+        header(&jar, "https://account.microsoftonline.com/"),
+        Some(String::from("esctx=state42"))
+    );
+    assert_eq!(
+        header(&jar, "https://login.microsoftonline.com/common/login"),
+        Some(String::from("esctx=state42; buid=b1"))
+    );
+    assert_eq!(
+        header(
+            &jar,
+            "https://login.microsoftonline.com/common/oauth2/token"
+        ),
+        Some(String::from("esctx=state42; buid=b1; fpc=f1"))
+    );
+    assert_eq!(
+        header(&jar, "https://login.microsoftonline.com/commonplace"),
+        Some(String::from("esctx=state42"))
+    );
+    assert_eq!(header(&jar, "https://intra.epitech.eu/"), None);
+    assert_eq!(header(&jar, "https://evilmicrosoftonline.com/"), None);
+
+    jar.store(
+        &url("https://account.microsoftonline.com/"),
+        vec![
+            "esctx=deleted; Domain=microsoftonline.com; Expires=Thu, 01-Jan-1970 00:00:00 GMT",
+            "buid=b2; Path=/common",
+        ],
+    );
+    assert_eq!(
+        header(&jar, "https://login.microsoftonline.com/common/login"),
+        Some(String::from("buid=b1"))
+    );
+    assert_eq!(
+        header(&jar, "https://account.microsoftonline.com/common"),
+        Some(String::from("buid=b2"))
+    );
+}
+
+#[tokio::test]
+async fn cookie_authentication() {
+    let transport = FnTransport::new(|_| Err(Error::UnreachableRemote));
+    let authenticator = CookieAuthenticator::new("user=abc123");
+    let credentials = authenticator
+        .authenticate(&transport, ENDPOINT)
+        .await
+        .unwrap();
+    assert_eq!(credentials.cookie, "abc123");
+    assert!(!authenticator.renewable());
+    assert_eq!(
+        CookieAuthenticator::new("")
+            .authenticate(&transport, ENDPOINT)
+            .await,
+        Err(Error::CookieNotFound)
+    );
+}
+
+#[derive(Debug, Default)]
+struct Signing {
+    signed: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl Middleware for Signing {
+    async fn handle(
+        &self,
+        request: transport::Request,
+        next: &dyn Transport,
+    ) -> Result<transport::Response, Error> {
+        self.signed.fetch_add(1, Ordering::SeqCst);
+        next.send(request.header("X-Signature", "signed")).await
+    }
+}
+
+/// Fails the first authenticated request to each route with a server error.
+#[derive(Debug, Default)]
+struct FaultInjection {
+    failed: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl Middleware for FaultInjection {
+    async fn handle(
+        &self,
+        request: transport::Request,
+        next: &dyn Transport,
+    ) -> Result<transport::Response, Error> {
+        let first = {
+            let mut failed = self.failed.lock().unwrap();
+            let first = request.header_value("cookie").is_some() && !failed.contains(&request.url);
+            if first {
+                failed.push(request.url.clone());
+            }
+            first
+        };
+        if first {
+            return Ok(transport::Response::new(503, ""));
+        }
+        next.send(request).await
+    }
+}
+
+/// Authenticates like the intranet, and serves downloads only through `send_streaming`.
+#[derive(Debug, Default)]
+struct StreamingOnly {
+    streamed: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl Transport for StreamingOnly {
+    async fn send(&self, request: transport::Request) -> Result<transport::Response, Error> {
+        let response = match request.url.as_str() {
+            "https://intra.test/auth-0123456789" => {
+                transport::Response::new(302, "").header("Set-Cookie", "user=streamed; path=/")
+            }
+            "https://intra.test/user?format=json" => {
+                let body = include_str!("../fixtures/user.json");
+                transport::Response::new(200, body.replace("{login}", "first.last@epitech.eu"))
+            }
+            url => panic!("{} was buffered", url),
+        };
+        Ok(response)
+    }
+
+    async fn send_streaming(
+        &self,
+        request: transport::Request,
+    ) -> Result<transport::StreamingResponse, Error> {
+        assert_eq!(request.header_value("x-signature"), Some("signed"));
+        self.streamed.fetch_add(1, Ordering::SeqCst);
+        Ok(transport::Response::new(200, "%PDF-1.4").into())
+    }
+}
+
+#[tokio::test]
+async fn streaming_through_middlewares() {
+    let transport = StreamingOnly::default();
+    let streamed = transport.streamed.clone();
+    let log = StatusLog::default();
+    let statuses = log.statuses.clone();
+    let client = Client::builder()
+        .endpoint("https://intra.test")
+        .autologin("https://intra.test/auth-0123456789")
+        .transport(transport)
+        .middleware(log)
+        .middleware(Signing::default())
+        .authenticate()
+        .await
+        .unwrap();
+    let download = client.download("/file/bsq.pdf").await.unwrap();
+    assert_eq!(download.bytes().await.unwrap(), b"%PDF-1.4");
+    assert_eq!(streamed.load(Ordering::SeqCst), 1);
+    assert_eq!(statuses.lock().unwrap().last(), Some(&200));
+}
+
+#[tokio::test]
+async fn foreign_hosts_without_cookie() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let log = sent.clone();
+    let transport = FnTransport::new(move |request: transport::Request| {
+        let cookie = request.header_value("cookie").map(String::from);
+        log.lock().unwrap().push((request.url.clone(), cookie));
+        let response = match request.url.as_str() {
+            "https://intra.test/auth-0123456789" => {
+                transport::Response::new(302, "").header("Set-Cookie", "user=secret; path=/")
+            }
+            "https://intra.test/user?format=json" => {
+                let body = include_str!("../fixtures/user.json");
+                transport::Response::new(200, body.replace("{login}", "first.last@epitech.eu"))
+            }
+            _ => transport::Response::new(200, "{}"),
+        };
+        Ok(response)
+    });
+    let client = Client::builder()
+        .endpoint("https://intra.test")
+        .autologin("https://intra.test/auth-0123456789")
+        .transport(transport)
+        .authenticate()
+        .await
+        .unwrap();
+    sent.lock().unwrap().clear();
+
+    client
+        .download("https://cdn.test/picture.jpg")
+        .await
+        .unwrap();
+    client
+        .make_request("https://intra.test.evil.test/user")
+        .await
+        .unwrap();
+    client.make_request("http://intra.test/user").await.unwrap();
+    client
+        .make_request("https://intra.test/user")
+        .await
+        .unwrap();
+    let cookies: Vec<_> = sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, cookie)| cookie.clone())
+        .collect();
+    assert_eq!(
+        cookies,
+        [None, None, None, Some(String::from("user=secret"))]
+    );
+}
+
+#[tokio::test]
+async fn transport_middlewares() {
+    let transport = FnTransport::new(|request: transport::Request| {
+        assert_eq!(request.header_value("x-signature"), Some("signed"));
+        let response = match request.url.as_str() {
+            "https://intra.test/auth-0123456789" => transport::Response::new(302, "")
+                .header("Location", "/")
+                .header("Set-Cookie", "user=double; path=/"),
+            "https://intra.test/user?format=json" => {
+                assert_eq!(request.header_value("cookie"), Some("user=double"));
+                let body = include_str!("../fixtures/user.json");
+                transport::Response::new(200, body.replace("{login}", "first.last@epitech.eu"))
+            }
+            _ => transport::Response::new(404, ""),
+        };
+        Ok(response)
+    });
+    let signing = Signing::default();
+    let signed = signing.signed.clone();
+    let faults = FaultInjection::default();
+    let failed = faults.failed.clone();
+    let client = Client::builder()
+        .endpoint("https://intra.test")
+        .autologin("https://intra.test/auth-0123456789")
+        .transport(transport)
+        .retry_policy(RetryPolicy::new().base_delay(Duration::from_millis(1)))
+        .middleware(signing)
+        .middleware(faults)
+        .authenticate()
+        .await
+        .unwrap();
+    assert_eq!(client.session().cookie, "double");
+    assert_eq!(client.session().login, "first.last@epitech.eu");
+    assert_eq!(
+        client.make_request("/unknown").await,
+        Err(Error::NotFound(String::from("/unknown")))
+    );
+    // The faults are injected behind the signing middleware, and each of them is retried once.
+    assert_eq!(failed.lock().unwrap().len(), 2);
+    assert_eq!(signed.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn location_and_promo_codes() {
+    for location in Location::all_known() {
+        assert_eq!(location.to_string().parse(), Ok(location));
+    }
+    for promo in Promo::all_known() {
+        assert_eq!(promo.to_string().parse(), Ok(promo));
+    }
+    assert_eq!("FR/PAR".parse(), Ok(Location::Paris));
+    assert_eq!(
+        "FR/MLH".parse(),
+        Ok(Location::Other(String::from("FR/MLH")))
+    );
+    assert_eq!(
+        "Paris".parse::<Location>(),
+        Err(ParseLocationError(String::from("Paris")))
+    );
+    assert_eq!("tek4".parse(), Ok(Promo::Other(String::from("tek4"))));
+    assert_eq!("".parse::<Promo>(), Err(ParsePromoError(String::new())));
+
+    let data = r#"[{"code":"FR/PAR","title":"Paris","students":"1337"},{"code":"FR/MLH","title":"Mulhouse","students":"42"}]"#;
+    let list: Vec<response::LocationEntry> = json::from_str(data).unwrap();
+    assert_eq!(list[0].code, Location::Paris);
+    assert_eq!(list[0].students, 1337);
+    assert_eq!(list[1].code, Location::Other(String::from("FR/MLH")));
+    assert_eq!(json::to_string(&list[1].code).unwrap(), r#""FR/MLH""#);
+
+    // Codes coming from the intranet are never rejected, even when malformed.
+    let location: Location = json::from_str(r#""Online""#).unwrap();
+    assert_eq!(location, Location::Other(String::from("Online")));
+    let promo: Promo = json::from_str("\"\"").unwrap();
+    assert_eq!(promo, Promo::Other(String::new()));
+    let promo: Promo = json::from_str(r#""tek2""#).unwrap();
+    assert_eq!(promo, Promo::Tek2);
+}
+
+#[test]
+fn intranet_failures() {
+    let failure = |status, body| Error::from_intranet_failure(status, "/module/x", body);
+    assert_eq!(
+        failure(403, r#"{"error":"Registration is closed for this module"}"#),
+        Error::RegistrationClosed
+    );
+    assert_eq!(
+        failure(403, r#"{"message":"Les inscriptions sont fermées"}"#),
+        Error::RegistrationClosed
+    );
+    assert_eq!(
+        failure(403, r#"{"error":"This activity is full"}"#),
+        Error::RegistrationFull
+    );
+    assert_eq!(
+        failure(
+            403,
+            r#"{"error":"Il n'y a plus de place pour cet événement"}"#
+        ),
+        Error::RegistrationFull
+    );
+    assert_eq!(
+        failure(
+            403,
+            r#"{"error":"You don't have enough credits to register to this module"}"#
+        ),
+        Error::MissingCredits
+    );
+    assert_eq!(
+        failure(403, r#"{"error":"Vous n'avez pas assez de crédits"}"#),
+        Error::MissingCredits
+    );
+    assert_eq!(
+        failure(403, r#"{"error":"Your group is incomplete"}"#),
+        Error::RequestRefused(403, String::from("Your group is incomplete"))
+    );
+    assert_eq!(
+        failure(200, r#"{"error":"Your group is incomplete"}"#),
+        Error::RequestRefused(200, String::from("Your group is incomplete"))
+    );
+    assert_eq!(
+        failure(500, "<h1>Internal Server Error</h1>"),
+        Error::ServerError(500)
+    );
+    assert_eq!(
+        failure(404, "<h1>Not Found</h1>"),
+        Error::NotFound(String::from("/module/x"))
+    );
+    assert_eq!(failure(401, "{}"), Error::Unauthorized);
+}
+
+#[test]
+fn token_refusals() {
+    use response::TokenValidation;
+    let refusal = |message: &str| {
+        TokenValidation::from_refusal(&json::json!({ "error": message }).to_string())
+    };
+    assert_eq!(refusal("Invalid token"), Some(TokenValidation::WrongToken));
+    assert_eq!(
+        refusal("Le token est incorrect"),
+        Some(TokenValidation::WrongToken)
+    );
+    assert_eq!(
+        refusal("You have already validated this token"),
+        Some(TokenValidation::AlreadyValidated)
+    );
+    assert_eq!(
+        refusal("Vous avez déjà validé ce token"),
+        Some(TokenValidation::AlreadyValidated)
+    );
+    assert_eq!(
+        refusal("The token validation period is over"),
+        Some(TokenValidation::WindowClosed)
+    );
+    assert_eq!(
+        refusal("La validation des tokens est fermée"),
+        Some(TokenValidation::WindowClosed)
+    );
+    assert_eq!(refusal("This event overlaps another one"), None);
+    assert_eq!(refusal("You are not registered to this event"), None);
+}
+
+#[test]
+fn serde_adapters() {
+    use chrono::NaiveDateTime;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Sample {
+        #[serde(with = "crate::serde_utils::number")]
+        credits: u32,
+        #[serde(default, with = "crate::serde_utils::option_number")]
+        grade: Option<f32>,
+        #[serde(default, with = "crate::serde_utils::option_number")]
+        year: Option<u32>,
+        #[serde(default, with = "crate::serde_utils::option_date")]
+        date: Option<NaiveDate>,
+        #[serde(default, with = "crate::serde_utils::option_datetime")]
+        begin: Option<NaiveDateTime>,
+        #[serde(default, with = "crate::serde_utils::flag")]
+        flag: bool,
+    }
+
+    let begin = NaiveDate::from_ymd_opt(2020, 1, 13)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap();
+    let sample: Sample = json::from_str(
+        r#"{"credits":"8","grade":"12.5","year":2019,"date":"2020-01-13","begin":"2020-01-13 09:00:00","flag":"1"}"#,
+    )
+    .unwrap();
+    assert_eq!(sample.credits, 8);
+    assert_eq!(sample.grade, Some(12.5));
+    assert_eq!(sample.year, Some(2019));
+    assert_eq!(sample.date, NaiveDate::from_ymd_opt(2020, 1, 13));
+    assert_eq!(sample.begin, Some(begin));
+    assert!(sample.flag);
+
+    let sample: Sample =
+        json::from_str(r#"{"credits":8,"grade":12.5,"year":"2019","flag":true}"#).unwrap();
+    assert_eq!(sample.credits, 8);
+    assert_eq!(sample.grade, Some(12.5));
+    assert_eq!(sample.year, Some(2019));
+    assert!(sample.flag);
+
+    let sample: Sample = json::from_str(
+        r#"{"credits":" 0 ","grade":"","year":null,"date":"","begin":null,"flag":"0"}"#,
+    )
+    .unwrap();
+    assert_eq!(sample.credits, 0);
+    assert_eq!(sample.grade, None);
+    assert_eq!(sample.year, None);
+    assert_eq!(sample.date, None);
+    assert_eq!(sample.begin, None);
+    assert!(!sample.flag);
+
+    let sample: Sample = json::from_str(r#"{"credits":0,"flag":null}"#).unwrap();
+    assert_eq!(
+        (sample.grade, sample.date, sample.flag),
+        (None, None, false)
+    );
+
+    assert!(json::from_str::<Sample>(r#"{"credits":null}"#).is_err());
+    assert!(json::from_str::<Sample>(r#"{"credits":""}"#).is_err());
+    assert!(json::from_str::<Sample>(r#"{"credits":"eight"}"#).is_err());
+    assert!(json::from_str::<Sample>(r#"{"credits":0,"year":-1}"#).is_err());
+    assert!(json::from_str::<Sample>(r#"{"credits":0,"date":"13/01/2020"}"#).is_err());
+    assert!(json::from_str::<Sample>(r#"{"credits":0,"flag":"maybe"}"#).is_err());
+}
+
+#[test]
+fn html_adapters() {
+    use crate::serde_utils::{decode_entities, strip_html};
+    assert_eq!(decode_entities("l&#39;activit&eacute;"), "l'activité");
+    assert_eq!(decode_entities("l&#x27;activit&#xE9;"), "l'activité");
+    assert_eq!(decode_entities("Tom &amp; Jerry"), "Tom & Jerry");
+    assert_eq!(decode_entities("&amp;#39;"), "&#39;");
+    assert_eq!(decode_entities("R&D &unknown; & co"), "R&D &unknown; & co");
+
+    assert_eq!(
+        strip_html(
+            r#"<a href="/user/jane.doe@epitech.eu/" class="user">Jane Doe</a> a ajouté une note à l&#39;activité <a href="/module/2019/B-CPE-110/STG-1-1/acti-350001/">Bistro-matic</a>"#
+        ),
+        "Jane Doe a ajouté une note à l'activité Bistro-matic"
+    );
+    assert_eq!(
+        strip_html(
+            "<span class=\"title\"><a href=\"/module/2019/B-PRO-300/STG-3-1/\">Le module <b>B-PRO-300</b></a></span>\n   est ouvert"
+        ),
+        "Le module B-PRO-300 est ouvert"
+    );
+    assert_eq!(
+        strip_html("Note : 12 &lt; 20&nbsp;&#x2014; <i>d&eacute;j&agrave; vue</i>"),
+        "Note : 12 < 20 — déjà vue"
+    );
+
+    let alert: response::NotificationAlert = json::from_str(
+        r#"{"id":"42","title":"Vous n&#x27;avez pas valid\u00e9 <a href=\"/module/2019/B-PRO-300/STG-3-1/acti-360401/\">l&#39;activit\u00e9</a>"}"#,
+    )
+    .unwrap();
+    assert_eq!(alert.title, "Vous n'avez pas validé l'activité");
+}
+
+#[tokio::test]
+async fn validate_event_token() {
+    use response::TokenValidation;
+    let intranet = MockIntranet::start();
+    let client = intranet.client().authenticate().await.unwrap();
+    let event = EventRef {
+        scolaryear: 2019,
+        codemodule: String::from("B-PRO-300"),
+        codeinstance: String::from("STG-3-1"),
+        codeacti: String::from("acti-360401"),
+        codeevent: String::from("event-371234"),
+    };
+
+    let validation = client.validate_event_token(&event, "00000000").await;
+    assert_eq!(validation, Ok(TokenValidation::WrongToken));
+    let validation = client
+        .validate_event_token(&event, MockIntranet::TOKEN)
+        .await;
+    assert_eq!(validation, Ok(TokenValidation::Accepted));
+    let validation = client
+        .validate_event_token(&event, MockIntranet::TOKEN)
+        .await;
+    assert_eq!(validation, Ok(TokenValidation::AlreadyValidated));
+
+    intranet.refuse(
+        format!("{}/token", event.path()),
+        200,
+        r#"{"error":"The token validation period is over"}"#,
+    );
+    let validation = client
+        .validate_event_token(&event, MockIntranet::TOKEN)
+        .await;
+    assert_eq!(validation, Ok(TokenValidation::WindowClosed));
+    intranet.refuse(
+        format!("{}/token", event.path()),
+        200,
+        r#"{"error":"You are not registered to this event"}"#,
+    );
+    let validation = client
+        .validate_event_token(&event, MockIntranet::TOKEN)
+        .await;
+    assert_eq!(
+        validation,
+        Err(Error::RequestRefused(
+            200,
+            String::from("You are not registered to this event")
+        ))
+    );
+}
+
+#[tokio::test]
+async fn registrations() {
+    let intranet = MockIntranet::start();
+    let client = intranet.client().authenticate().await.unwrap();
+
+    let module = "/module/2019/B-PRO-300/STG-3-1";
+    assert_eq!(
+        client.register_module(2019, "B-PRO-300", "STG-3-1").await,
+        Ok(())
+    );
+    assert!(intranet.is_registered(module));
+    assert_eq!(
+        client.register_module(2019, "B-PRO-300", "STG-3-1").await,
+        Err(Error::RequestRefused(
+            403,
+            String::from("You are already registered")
+        ))
+    );
+    assert_eq!(
+        client.unregister_module(2019, "B-PRO-300", "STG-3-1").await,
+        Ok(())
+    );
+    assert!(!intranet.is_registered(module));
+
+    let event = EventRef {
+        scolaryear: 2019,
+        codemodule: String::from("B-PRO-300"),
+        codeinstance: String::from("STG-3-1"),
+        codeacti: String::from("acti-360401"),
+        codeevent: String::from("event-371234"),
+    };
+    assert_eq!(client.register_event(&event).await, Ok(()));
+    assert!(intranet.is_registered(&event.path()));
+    assert_eq!(client.unregister_event(&event).await, Ok(()));
+    assert!(!intranet.is_registered(&event.path()));
+
+    let project = "/module/2019/B-CPE-110/STG-1-1/acti-350001/project";
+    let registration = client
+        .register_project(
+            2019,
+            "B-CPE-110",
+            "STG-1-1",
+            "acti-350001",
+            &["jane.doe@epitech.eu"],
+        )
+        .await;
+    assert_eq!(registration, Ok(()));
+    assert!(intranet.is_registered(project));
+    let unregistration = client
+        .unregister_project(2019, "B-CPE-110", "STG-1-1", "acti-350001")
+        .await;
+    assert_eq!(unregistration, Ok(()));
+    assert!(!intranet.is_registered(project));
+
+    intranet.refuse(
+        format!("{}/register", module),
+        403,
+        r#"{"error":"Registration is closed for this module"}"#,
+    );
+    assert_eq!(
+        client.register_module(2019, "B-PRO-300", "STG-3-1").await,
+        Err(Error::RegistrationClosed)
+    );
+    intranet.refuse(
+        format!("{}/register", event.path()),
+        403,
+        r#"{"error":"This activity is full"}"#,
+    );
+    assert_eq!(
+        client.register_event(&event).await,
+        Err(Error::RegistrationFull)
+    );
+
+    // Refusals with a successful status, and failures without a message, are reported too.
+    intranet.refuse(
+        format!("{}/unregister", module),
+        200,
+        r#"{"error":"You are not registered"}"#,
+    );
+    assert_eq!(
+        client.unregister_module(2019, "B-PRO-300", "STG-3-1").await,
+        Err(Error::RequestRefused(
+            200,
+            String::from("You are not registered")
+        ))
+    );
+    intranet.refuse(
+        format!("{}/unregister", event.path()),
+        500,
+        "<h1>Internal Server Error</h1>",
+    );
+    assert_eq!(
+        client.unregister_event(&event).await,
+        Err(Error::ServerError(500))
+    );
+}
+
 #[tokio::test]
 async fn auth_working_link() {
     let client = setup_client().await;
     assert!(client.is_ok());
 }
 
+#[tokio::test]
+async fn fetch_unknown_route() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let result = client.make_request("/this/route/does/not/exist").await;
+    assert_eq!(
+        result.unwrap_err(),
+        Error::NotFound(String::from("/this/route/does/not/exist"))
+    );
+}
+
 #[tokio::test]
 async fn fetch_student_list() {
     let client = setup_client().await;
@@ -41,23 +1198,126 @@ async fn fetch_student_list() {
 }
 
 #[tokio::test]
-async fn fetch_city_list() {
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    struct Region {
-        code: Location,
-        title: String,
-        students: String,
-    }
+async fn stream_student_list() {
     let client = setup_client().await;
     assert!(client.is_ok());
     let client = client.unwrap();
-    let list = client
-        .make_request("/user/filter/location?active=true")
+    let list: Result<Vec<_>, Error> = client
+        .fetch_student_list()
+        .location(Location::Strasbourg)
+        .promo(Promo::Tek2)
+        .year(2019)
+        .stream()
+        .take(60)
+        .try_collect()
         .await;
     assert!(list.is_ok());
-    let list = list.unwrap();
-    let data = json::from_str::<Vec<Region>>(list.as_str());
-    assert!(data.is_ok());
+    assert!(list.unwrap().len() <= 60);
+}
+
+#[tokio::test]
+async fn stream_matches_send() {
+    let intranet = MockIntranet::start();
+    intranet.page_size(1);
+    let client = intranet.client().authenticate().await.unwrap();
+    let logins = |list: Vec<response::UserEntry>| {
+        list.into_iter()
+            .map(|entry| entry.login)
+            .collect::<Vec<_>>()
+    };
+    let sent = client.fetch_student_list().year(2019).send().await.unwrap();
+    let streamed: Vec<_> = client
+        .fetch_student_list()
+        .year(2019)
+        .stream()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(logins(sent.clone()).len(), 5);
+    assert_eq!(logins(streamed), logins(sent));
+    assert_eq!(intranet.requests("/user/filter/user"), 10);
+}
+
+#[tokio::test]
+async fn stream_tolerates_total_changes() {
+    let intranet = MockIntranet::start();
+    let client = intranet.client().authenticate().await.unwrap();
+    let mut list: json::Value =
+        json::from_str(include_str!("../fixtures/user_filter_user.json")).unwrap();
+
+    let mut stream = Box::pin(client.fetch_student_list().year(2019).stream());
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.login, MockIntranet::LOGIN);
+    // A student leaves the list once the first page (of 2) has been served.
+    list["items"].as_array_mut().unwrap().remove(0);
+    list["total"] = json::json!(4);
+    intranet.route("/user/filter/user", list.to_string());
+    let rest: Vec<_> = stream.try_collect().await.unwrap();
+    let rest: Vec<_> = rest.into_iter().map(|entry| entry.login).collect();
+    // The list shifted, so `jane.doe` is skipped, but the stream ends at the new total.
+    assert_eq!(
+        rest,
+        [
+            "nicolas.polomack@epitech.eu",
+            "john.smith@epitech.eu",
+            "camille.martin@epitech.eu"
+        ]
+    );
+    assert_eq!(intranet.requests("/user/filter/user"), 2);
+
+    // A student joins the list: the stream goes on until the new total is reached.
+    let mut stream = Box::pin(client.fetch_student_list().year(2019).stream());
+    stream.next().await.unwrap().unwrap();
+    let items = list["items"].as_array_mut().unwrap();
+    for login in &["new.student@epitech.eu", "other.student@epitech.eu"] {
+        let mut student = items[0].clone();
+        student["login"] = json::json!(login);
+        items.push(student);
+    }
+    list["total"] = json::json!(6);
+    intranet.route("/user/filter/user", list.to_string());
+    let rest: Vec<_> = stream.try_collect().await.unwrap();
+    assert_eq!(rest.len(), 5);
+    assert_eq!(rest.last().unwrap().login, "other.student@epitech.eu");
+    assert_eq!(intranet.requests("/user/filter/user"), 5);
+}
+
+#[tokio::test]
+async fn stream_stops_early() {
+    let intranet = MockIntranet::start();
+    let client = intranet.client().authenticate().await.unwrap();
+    let list: Vec<_> = client
+        .fetch_student_list()
+        .year(2019)
+        .stream()
+        .take(3)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(list.len(), 3);
+    // The third student is on the second page (of 2), and the third page is never requested.
+    assert_eq!(intranet.requests("/user/filter/user"), 2);
+
+    let list: Vec<_> = client
+        .fetch_student_list()
+        .year(2019)
+        .stream()
+        .take(2)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(intranet.requests("/user/filter/user"), 3);
+}
+
+#[tokio::test]
+async fn fetch_city_list() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let list = client.fetch_locations().await;
+    assert!(list.is_ok());
+    assert!(!list.unwrap().is_empty());
 }
 
 #[tokio::test]
@@ -81,13 +1341,13 @@ async fn fetch_all_students_list() {
     assert!(client.is_ok());
     let client = client.unwrap();
     let mut list = Vec::default();
-    for promo in Promo::into_enum_iter() {
-        for location in Location::into_enum_iter() {
+    for promo in Promo::all_known() {
+        for location in Location::all_known() {
             println!("{} {}", promo, location);
             let students = client
                 .fetch_student_list()
                 .location(location)
-                .promo(promo)
+                .promo(promo.clone())
                 .year(2019)
                 .send()
                 .await;
@@ -121,6 +1381,27 @@ async fn fetch_other_student_data() {
     assert!(list.is_ok());
 }
 
+#[tokio::test]
+async fn download_own_picture() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let data = client.fetch_student_data().send().await;
+    assert!(data.is_ok());
+    let data = data.unwrap();
+    let download = client.download(data.picture.as_str()).await;
+    assert!(download.is_ok());
+    let download = download.unwrap();
+    let expected = download.content_length();
+    let mut picture = Vec::new();
+    let written = download.write_to(&mut picture).await;
+    assert!(written.is_ok());
+    assert_eq!(written.unwrap(), picture.len() as u64);
+    if let Some(expected) = expected {
+        assert_eq!(expected, picture.len() as u64);
+    }
+}
+
 // #[tokio::test]
 // async fn fetch_own_student_netsoul() {
 //     let client = setup_client().await;
@@ -130,6 +1411,111 @@ async fn fetch_other_student_data() {
 //     assert!(list.is_some());
 // }
 
+#[tokio::test]
+async fn fetch_planning() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let events = client
+        .fetch_planning()
+        .start(NaiveDate::from_ymd_opt(2019, 10, 7).unwrap())
+        .end(NaiveDate::from_ymd_opt(2019, 10, 13).unwrap())
+        .only_my_modules(true)
+        .send()
+        .await;
+    assert!(events.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_module() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let module = client.fetch_module(2019, "B-PRO-300", "STG-3-1").await;
+    assert!(module.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_activity() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let module = client.fetch_module(2019, "B-PRO-300", "STG-3-1").await;
+    assert!(module.is_ok());
+    let module = module.unwrap();
+    if let Some(activity) = module.activities.first() {
+        let activity = client
+            .fetch_activity(
+                module.scolaryear,
+                module.codemodule.as_str(),
+                module.codeinstance.as_str(),
+                activity.codeacti.as_str(),
+            )
+            .await;
+        assert!(activity.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn fetch_project() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let project = client
+        .fetch_project(2019, "B-CPE-110", "STG-1-1", "acti-350001")
+        .await;
+    assert!(project.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_project_files() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let files = client
+        .fetch_project_files(2019, "B-CPE-110", "STG-1-1", "acti-350001")
+        .await;
+    assert!(files.is_ok());
+}
+
+#[tokio::test]
+async fn download_project_file() {
+    let client = MockIntranet::start().client().authenticate().await.unwrap();
+    let files = client
+        .fetch_project_files(2019, "B-CPE-110", "STG-1-1", "acti-350001")
+        .await
+        .unwrap();
+    let url = files[0].download_url(client.endpoint());
+    assert!(url.starts_with(client.endpoint()));
+    let download = client.download(url).await.unwrap();
+    assert_eq!(download.content_type(), Some("application/pdf"));
+    let bytes = download.bytes().await.unwrap();
+    assert_eq!(bytes, include_bytes!("../fixtures/bsq.pdf").to_vec());
+}
+
+#[tokio::test]
+async fn fetch_many_student_data() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let progress = Arc::new(AtomicUsize::new(0));
+    let counter = progress.clone();
+    let results = client
+        .fetch_many_student_data(vec![
+            "nicolas.polomack@epitech.eu",
+            "this.login.does.not.exist@epitech.eu",
+        ])
+        .parallelism(2)
+        .on_progress(move |done, _| counter.store(done, Ordering::SeqCst))
+        .send()
+        .await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(progress.load(Ordering::SeqCst), 2);
+    assert_eq!(results[0].0, "nicolas.polomack@epitech.eu");
+    assert!(results[0].1.is_ok());
+    assert!(results[1].1.is_err());
+}
+
 #[tokio::test]
 async fn fetch_other_student_netsoul() {
     let client = setup_client().await;
@@ -141,6 +1527,38 @@ async fn fetch_other_student_netsoul() {
     assert!(list.is_ok());
 }
 
+#[test]
+fn netsoul_aggregation() {
+    let entries: Vec<response::UserNetsoulEntry> = json::from_str(
+        "[[1570399200,3600,0,0,0,0],[1570485600,7200,0,0,0,0],[1571004000,1800,0,0,0,0]]",
+    )
+    .unwrap();
+    let dates: Vec<_> = entries.iter().map(|entry| entry.date).collect();
+    assert_eq!(
+        dates,
+        vec![
+            NaiveDate::from_ymd_opt(2019, 10, 7).unwrap(),
+            NaiveDate::from_ymd_opt(2019, 10, 8).unwrap(),
+            NaiveDate::from_ymd_opt(2019, 10, 14).unwrap(),
+        ]
+    );
+    let weeks = netsoul::weekly_totals(&entries);
+    assert_eq!(weeks.len(), 2);
+    assert_eq!(
+        weeks[0].start,
+        NaiveDate::from_ymd_opt(2019, 10, 7).unwrap()
+    );
+    assert_eq!(weeks[0].active_hours(), 3.0);
+    let nsstat = response::UserDataNsStat {
+        nslog_norm: 10.0,
+        ..response::UserDataNsStat::default()
+    };
+    assert_eq!(weeks[1].norm_ratio(&nsstat), Some(0.05));
+    let averages = netsoul::rolling_average(&entries, 2);
+    assert_eq!(averages[1].1, 5400.0);
+    assert_eq!(averages[2].1, 900.0);
+}
+
 #[tokio::test]
 async fn fetch_other_student_notes() {
     let client = setup_client().await;
@@ -152,6 +1570,22 @@ async fn fetch_other_student_notes() {
     assert!(list.is_ok());
 }
 
+#[tokio::test]
+async fn fetch_mark_detail() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let notes = client
+        .fetch_student_notes("nicolas.polomack@epitech.eu")
+        .await;
+    assert!(notes.is_ok());
+    let notes = notes.unwrap();
+    if let Some(mark) = notes.notes.first() {
+        let detail = client.fetch_mark_detail(mark).await;
+        assert!(detail.is_ok());
+    }
+}
+
 #[tokio::test]
 async fn fetch_other_student_binomes() {
     let client = setup_client().await;
@@ -163,6 +1597,42 @@ async fn fetch_other_student_binomes() {
     assert!(list.is_ok());
 }
 
+#[tokio::test]
+async fn fetch_own_student_notification_messages() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let list = client.fetch_own_student_notification_messages().await;
+    assert!(list.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_own_student_notification_alerts() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let list = client.fetch_own_student_notification_alerts().await;
+    assert!(list.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_own_student_notification_missed() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let list = client.fetch_own_student_notification_missed().await;
+    assert!(list.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_own_student_notification_coming() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let list = client.fetch_own_student_notification_coming().await;
+    assert!(list.is_ok());
+}
+
 // #[tokio::test]
 #[allow(unused)]
 async fn fetch_all_gpas() {
@@ -199,11 +1669,9 @@ async fn fetch_all_gpas() {
             let gpa = data
                 .gpa
                 .expect("No GPA field.")
-                .get(0)
+                .first()
                 .expect("No GPA elements.")
-                .gpa
-                .parse()
-                .expect("Can't map GPA to a float.");
+                .gpa;
             (firstname, lastname, login, gpa)
         })
         .collect();
@@ -232,3 +1700,308 @@ async fn fetch_available_courses() {
         .await;
     assert!(list.is_ok());
 }
+
+#[tokio::test]
+async fn fetch_autologin() {
+    let client = setup_client().await;
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    let session = client.session();
+    let link = client.fetch_autologin().await;
+    assert!(link.is_ok());
+    assert_eq!(client.session(), session);
+}
+
+#[tokio::test]
+async fn regenerate_autologin() {
+    let intranet = MockIntranet::start();
+    let previous = intranet.autologin();
+    let client = intranet.client().authenticate().await.unwrap();
+    let link = client.regenerate_autologin().await.unwrap();
+    assert_ne!(link.autologin, previous);
+    assert_eq!(link.autologin, intranet.autologin());
+    assert_eq!(client.session().autologin, Some(link.autologin));
+    let stale = Client::builder()
+        .endpoint(intranet.url())
+        .autologin(previous)
+        .authenticate()
+        .await;
+    assert_eq!(stale.unwrap_err(), Error::CookieNotFound);
+    assert!(client.reauthenticate().await.is_ok());
+    assert!(client.fetch_own_student_notes().await.is_ok());
+
+    // Clients authenticating otherwise keep their authenticator.
+    let cookie = client.session().cookie;
+    let client = Client::builder()
+        .endpoint(intranet.url())
+        .authenticator(CookieAuthenticator::new(cookie))
+        .authenticate()
+        .await
+        .unwrap();
+    let link = client.regenerate_autologin().await.unwrap();
+    assert_eq!(client.session().autologin, Some(link.autologin));
+    let authenticator = client.authenticator.read().unwrap().clone().unwrap();
+    assert_eq!(authenticator.autologin(), None);
+}
+
+#[tokio::test]
+async fn restored_authenticator() {
+    let intranet = MockIntranet::start();
+    let client = intranet.client().authenticate().await.unwrap();
+    let session = client.session();
+
+    // The autologin link of the session is replayed once the cookie expires.
+    let restored = Client::builder()
+        .endpoint(intranet.url())
+        .restore(session.clone())
+        .unwrap();
+    intranet.expire_session();
+    assert!(restored.make_request("/user").await.is_ok());
+
+    // Unless another authenticator is configured, which is then the only one used.
+    let autologin = intranet.autologin();
+    let auth_route = &autologin[intranet.url().len()..];
+    let requests = intranet.requests(auth_route);
+    let restored = Client::builder()
+        .endpoint(intranet.url())
+        .authenticator(CookieAuthenticator::new("stale"))
+        .restore(session)
+        .unwrap();
+    intranet.expire_session();
+    let user = restored.make_request("/user").await;
+    assert_eq!(user, Err(Error::Unauthorized));
+    assert_eq!(intranet.requests(auth_route), requests);
+}
+
+#[tokio::test]
+async fn reauthenticate_on_expiry() {
+    let intranet = MockIntranet::start();
+    let client = intranet.client().authenticate().await.unwrap();
+    let autologin = intranet.autologin();
+    let auth_route = &autologin[intranet.url().len()..];
+    let cookie = client.session().cookie;
+    assert_eq!(intranet.requests(auth_route), 1);
+
+    // The cookie expires mid-run: concurrent requests all get a 401, but only one of them re-authenticates.
+    intranet.expire_session();
+    let requests = (0..4).map(|_| client.make_request("/user"));
+    let responses = future::join_all(requests).await;
+    assert!(responses.iter().all(Result::is_ok));
+    assert_eq!(intranet.requests(auth_route), 2);
+    assert_ne!(client.session().cookie, cookie);
+
+    intranet.expire_session();
+    let registration = client.register_module(2019, "B-PRO-300", "STG-3-1").await;
+    assert_eq!(registration, Ok(()));
+    assert_eq!(intranet.requests(auth_route), 3);
+
+    // A restored session without a cookie authenticates before its first request.
+    let session = Session {
+        cookie: String::default(),
+        ..client.session()
+    };
+    let restored = intranet.client().restore(session).unwrap();
+    assert!(restored.make_request("/user").await.is_ok());
+    assert_eq!(intranet.requests(auth_route), 4);
+
+    // Being forbidden to access a resource has nothing to do with the session.
+    intranet.refuse("/module/2019/B-PRO-300/STG-3-1", 403, "{}");
+    let module = client.make_request("/module/2019/B-PRO-300/STG-3-1").await;
+    assert_eq!(module, Err(Error::Forbidden));
+    assert_eq!(intranet.requests(auth_route), 4);
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let intranet = MockIntranet::start();
+    let path =
+        std::env::temp_dir().join(format!("epitech-api-cassette-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let recorder = Recorder::new(ReqwestTransport::new().unwrap(), &path);
+    let client = intranet
+        .client()
+        .transport(recorder)
+        .authenticate()
+        .await
+        .unwrap();
+    // Every exchange is appended to the cassette as soon as it completes.
+    assert_eq!(Cassette::load(&path).unwrap().interactions.len(), 2);
+    let notes = client.fetch_own_student_notes().await.unwrap();
+    let link = client.fetch_autologin().await.unwrap();
+    let file = "/module/2019/B-CPE-110/STG-1-1/acti-350001/project/file/bsq.pdf";
+    let bytes = client.download(file).await.unwrap().bytes().await.unwrap();
+
+    let cassette = Cassette::load(&path).unwrap();
+    assert_eq!(cassette.interactions.len(), 5);
+    assert_eq!(cassette.interactions[0].request.url, "/auth-[REDACTED]");
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), 5);
+    assert!(!contents.contains("mock-session-cookie"));
+    assert!(!contents.contains(link.autologin.as_str()));
+    assert!(!contents.contains("\"Mock\""));
+    assert!(!contents.contains("first.last"));
+    assert!(!contents.contains("/file/userprofil/"));
+
+    let client = Client::builder()
+        .transport(Replayer::load(&path).unwrap())
+        .restore(client.session())
+        .unwrap();
+    let data = client.fetch_student_data().send().await.unwrap();
+    assert_ne!(data.login, MockIntranet::LOGIN);
+    assert!(data.login.starts_with("student-"));
+    assert_eq!(data.firstname, crate::vcr::REDACTED);
+    assert_eq!(data.picture, crate::vcr::REDACTED);
+    // The notes of the pseudonym are the ones recorded for the actual login.
+    let replayed = client.fetch_own_student_notes().await.unwrap();
+    assert_eq!(replayed.notes.len(), notes.notes.len());
+    // Titles are not personal data, and are kept as is.
+    assert_eq!(replayed.modules[0].title, notes.modules[0].title);
+    let replayed = client.fetch_student_notes(&data.login).await.unwrap();
+    assert_eq!(replayed.notes.len(), notes.notes.len());
+    let download = client.download(file).await.unwrap();
+    assert_eq!(download.content_type(), Some("application/pdf"));
+    assert_eq!(download.bytes().await.unwrap(), bytes);
+    let binomes = client.fetch_student_binomes(MockIntranet::LOGIN).await;
+    let url = format!("GET /user/{}/binome?format=json", data.login);
+    assert_eq!(binomes.unwrap_err(), Error::UnrecordedRequest(url));
+
+    // Recording again appends to the cassette.
+    let recorder = Recorder::new(ReqwestTransport::new().unwrap(), &path);
+    intranet
+        .client()
+        .transport(recorder)
+        .authenticate()
+        .await
+        .unwrap();
+    assert_eq!(Cassette::load(&path).unwrap().interactions.len(), 7);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn recorder_scrubs_secrets() {
+    let page = r#"<form method="post" action="/common/login">
+        <input type="hidden" name="flowToken" value="flow-secret">
+        <input type="hidden" name="ctx" value='ctx&amp;secret'>
+        <input type="hidden" name="locale" value="fr">
+    </form>"#;
+    let transport = move |request: transport::Request| {
+        let response = match (request.method.as_str(), request.url.as_str()) {
+            ("GET", "https://login.test/oauth2/authorize") => transport::Response::new(200, page)
+                .header(
+                    "Set-Cookie",
+                    "ESTSAUTH=estsauth-secret; domain=.login.test; path=/",
+                )
+                .header("Set-Cookie", "buid=buid-secret; path=/"),
+            ("POST", "https://login.test/common/login") => transport::Response::new(302, "")
+                .header(
+                    "Location",
+                    "https://intra.test/auth/office365/callback?code=code-secret&session_state=1",
+                ),
+            _ => transport::Response::new(200, r#"{"title":"Mock Student","credits":6}"#),
+        };
+        Ok(response)
+    };
+    let path =
+        std::env::temp_dir().join(format!("epitech-api-secrets-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let recorder = Recorder::new(FnTransport::new(transport), &path).scrub_field("title");
+    let authorize = transport::Request::new("GET", "https://login.test/oauth2/authorize");
+    let login = transport::Request::new("POST", "https://login.test/common/login").form(&[
+        ("flowToken", "flow-secret"),
+        ("ctx", "ctx&secret"),
+        ("locale", "fr"),
+        ("login", "first.last@epitech.eu"),
+        ("passwd", "hunter2"),
+    ]);
+    let callback = transport::Request::new(
+        "GET",
+        "https://intra.test/auth/office365/callback?code=code-secret&session_state=1",
+    );
+    for request in [authorize.clone(), login.clone(), callback.clone()].iter() {
+        recorder.send(request.clone()).await.unwrap();
+    }
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    for secret in &[
+        "estsauth-secret",
+        "buid-secret",
+        "flow-secret",
+        "ctx&amp;secret",
+        "ctx&secret",
+        "code-secret",
+        "hunter2",
+        "Mock Student",
+    ] {
+        assert!(!contents.contains(secret), "{} was recorded", secret);
+    }
+    assert!(contents.contains("ESTSAUTH=[REDACTED]; domain=.login.test; path=/"));
+    assert!(contents.contains("locale"));
+    let cassette = Cassette::load(&path).unwrap();
+    assert_eq!(
+        cassette.interactions[2].response.text(),
+        r#"{"credits":6,"title":"[REDACTED]"}"#
+    );
+
+    // The replayed page hands out redacted tokens, and the requests posting them back still match.
+    let replayer = Replayer::load(&path).unwrap();
+    let page = replayer.send(authorize).await.unwrap().text();
+    let fields = crate::auth::hidden_inputs(&page);
+    assert_eq!(
+        fields[0],
+        (String::from("flowToken"), String::from("[REDACTED]"))
+    );
+    assert_eq!(fields[2], (String::from("locale"), String::from("fr")));
+    let replayed = transport::Request::new("POST", "https://login.test/common/login").form(&[
+        ("flowToken", "[REDACTED]"),
+        ("ctx", "[REDACTED]"),
+        ("locale", "fr"),
+        ("login", "first.last@epitech.eu"),
+        ("passwd", "hunter2"),
+    ]);
+    assert_eq!(replayer.send(replayed).await.unwrap().status, 302);
+    assert_eq!(replayer.send(callback).await.unwrap().status, 200);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_client() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let intranet = runtime.enter(MockIntranet::start);
+    let client = crate::blocking::ClientBuilder::from(intranet.client()).authenticate();
+    assert!(client.is_ok());
+    let client = client.unwrap();
+    assert_eq!(client.session().login, MockIntranet::LOGIN);
+    assert!(client.fetch_own_student_notes().is_ok());
+    let list = client
+        .fetch_student_list()
+        .location(Location::Strasbourg)
+        .promo(Promo::Tek2)
+        .year(2019)
+        .send();
+    assert!(list.is_ok());
+    let list = list.unwrap();
+    let streamed: Result<Vec<_>, _> = client
+        .fetch_student_list()
+        .location(Location::Strasbourg)
+        .promo(Promo::Tek2)
+        .year(2019)
+        .iter()
+        .collect();
+    assert_eq!(streamed.unwrap().len(), list.len());
+    let results = client
+        .fetch_many_student_data(list.iter().map(|entry| entry.login.clone()))
+        .send();
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+    let data = client.fetch_student_data().send().unwrap();
+    let download = client.download(data.picture.as_str()).unwrap();
+    let mut picture = Vec::new();
+    let written = download.write_to(&mut picture);
+    assert_eq!(written, Ok(picture.len() as u64));
+    assert_eq!(
+        client
+            .fetch_student_notes("unknown@epitech.eu")
+            .unwrap_err(),
+        Error::NotFound(String::from("/user/unknown@epitech.eu/notes"))
+    );
+}
@@ -0,0 +1,388 @@
+//! The layer through which a `Client` exchanges requests with the intranet.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// A request sent to the intranet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    /// The HTTP method (eg. `GET`).
+    pub method: String,
+    /// The absolute URL of the request.
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// The form-encoded body of the request, if any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<(String, String)>,
+}
+
+/// A response received from the intranet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The raw body, serialized as a string if it is valid UTF-8 and as `{ "base64": "..." }` otherwise.
+    #[serde(with = "body")]
+    pub body: Vec<u8>,
+}
+
+/// A response whose body is read lazily, as returned by `Transport::send_streaming`.
+#[derive(Debug)]
+pub struct StreamingResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    body: StreamingBody,
+}
+
+#[derive(Debug)]
+enum StreamingBody {
+    Buffered(Option<Vec<u8>>),
+    Reqwest(reqwest::Response),
+}
+
+impl Request {
+    pub fn new<M: Into<String>, U: Into<String>>(method: M, url: U) -> Request {
+        Request {
+            method: method.into(),
+            url: url.into(),
+            headers: Vec::default(),
+            form: Vec::default(),
+        }
+    }
+
+    #[inline]
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Request {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    #[inline]
+    pub fn form(mut self, form: &[(&str, &str)]) -> Request {
+        self.form = form
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// The value of a header (the first one, if repeated), looked up case-insensitively.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl Response {
+    pub fn new<B: Into<Vec<u8>>>(status: u16, body: B) -> Response {
+        Response {
+            status,
+            headers: Vec::default(),
+            body: body.into(),
+        }
+    }
+
+    #[inline]
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Response {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// The value of a header (the first one, if repeated), looked up case-insensitively.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Every value of a repeated header (eg. `Set-Cookie`), looked up case-insensitively.
+    pub fn header_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the status code is in the 2xx range.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The body as text, with invalid UTF-8 sequences replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+impl StreamingResponse {
+    /// The value of a header (the first one, if repeated), looked up case-insensitively.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Whether the status code is in the 2xx range.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Reads the next chunk of the body, if any is left.
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        match self.body {
+            StreamingBody::Buffered(ref mut body) => {
+                Ok(body.take().filter(|body| !body.is_empty()))
+            }
+            StreamingBody::Reqwest(ref mut response) => {
+                let chunk = response.chunk().await?;
+                Ok(chunk.map(|chunk| chunk.to_vec()))
+            }
+        }
+    }
+}
+
+impl From<Response> for StreamingResponse {
+    fn from(response: Response) -> StreamingResponse {
+        StreamingResponse {
+            status: response.status,
+            headers: response.headers,
+            body: StreamingBody::Buffered(Some(response.body)),
+        }
+    }
+}
+
+/// (De)serializes a body as a string if it is valid UTF-8, and as base64 otherwise.
+mod body {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Body {
+        Text(String),
+        Binary { base64: String },
+    }
+
+    pub fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(body) {
+            Ok(text) => Body::Text(text.to_string()),
+            Err(_) => Body::Binary {
+                base64: base64::encode(body),
+            },
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match Body::deserialize(deserializer)? {
+            Body::Text(text) => Ok(text.into_bytes()),
+            Body::Binary { base64 } => base64::decode(base64).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Sends requests to the intranet on behalf of a `Client`, including the ones of its `Authenticator`.
+///
+/// Transports are given to `ClientBuilder::transport`, eg. to record or replay traffic (see the `vcr` module)
+/// or to substitute a test double (see `FnTransport`).
+/// Redirections must not be followed, so that the authenticators can see them.
+///
+/// File downloads (`Client::download`) go through `send_streaming`, which buffers the body using `send` by default.
+#[async_trait]
+pub trait Transport: fmt::Debug + Send + Sync {
+    async fn send(&self, request: Request) -> Result<Response, Error>;
+
+    /// Sends a request whose response body can be read lazily.
+    async fn send_streaming(&self, request: Request) -> Result<StreamingResponse, Error> {
+        let response = self.send(request).await?;
+        Ok(StreamingResponse::from(response))
+    }
+}
+
+/// A layer wrapping the transport of a `Client`, able to inspect or alter requests and responses
+/// (eg. logging, signing or fault injection).
+///
+/// Middlewares are added using `ClientBuilder::middleware`.
+#[async_trait]
+pub trait Middleware: fmt::Debug + Send + Sync {
+    /// Handles a request, usually by passing it on to `next`.
+    async fn handle(&self, request: Request, next: &dyn Transport) -> Result<Response, Error>;
+
+    /// Handles a request whose response body is read lazily (eg. a file download).
+    ///
+    /// By default, the request goes through `handle`, which sees the response with an empty body,
+    /// and the body is then read lazily from `next` unless `handle` returned a response with a body of its own.
+    async fn handle_streaming(
+        &self,
+        request: Request,
+        next: &dyn Transport,
+    ) -> Result<StreamingResponse, Error> {
+        let next = StreamingNext {
+            inner: next,
+            body: Mutex::default(),
+        };
+        let response = self.handle(request, &next).await?;
+        let body = next.body.lock().unwrap().take();
+        match body {
+            Some(body) if response.body.is_empty() => Ok(StreamingResponse {
+                status: response.status,
+                headers: response.headers,
+                body,
+            }),
+            _ => Ok(StreamingResponse::from(response)),
+        }
+    }
+}
+
+/// The transport given to `Middleware::handle` by `Middleware::handle_streaming`,
+/// which sends the requests using `send_streaming` and holds the body of the last response back.
+#[derive(Debug)]
+struct StreamingNext<'a> {
+    inner: &'a dyn Transport,
+    body: Mutex<Option<StreamingBody>>,
+}
+
+#[async_trait]
+impl Transport for StreamingNext<'_> {
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        let response = self.inner.send_streaming(request).await?;
+        *self.body.lock().unwrap() = Some(response.body);
+        Ok(Response {
+            status: response.status,
+            headers: response.headers,
+            body: Vec::default(),
+        })
+    }
+}
+
+/// A transport running a middleware in front of another transport.
+#[derive(Debug, Clone)]
+pub struct Layered {
+    middleware: Arc<dyn Middleware>,
+    inner: Arc<dyn Transport>,
+}
+
+impl Layered {
+    pub fn new(middleware: Arc<dyn Middleware>, inner: Arc<dyn Transport>) -> Layered {
+        Layered { middleware, inner }
+    }
+}
+
+#[async_trait]
+impl Transport for Layered {
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        self.middleware.handle(request, self.inner.as_ref()).await
+    }
+
+    async fn send_streaming(&self, request: Request) -> Result<StreamingResponse, Error> {
+        self.middleware
+            .handle_streaming(request, self.inner.as_ref())
+            .await
+    }
+}
+
+/// A transport answering requests using a function, without touching the network.
+pub struct FnTransport<F> {
+    handler: F,
+}
+
+impl<F> FnTransport<F>
+where
+    F: Fn(Request) -> Result<Response, Error> + Send + Sync,
+{
+    pub fn new(handler: F) -> FnTransport<F> {
+        FnTransport { handler }
+    }
+}
+
+impl<F> fmt::Debug for FnTransport<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FnTransport").finish()
+    }
+}
+
+#[async_trait]
+impl<F> Transport for FnTransport<F>
+where
+    F: Fn(Request) -> Result<Response, Error> + Send + Sync,
+{
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        (self.handler)(request)
+    }
+}
+
+/// The default transport, sending requests over the network using `reqwest`.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Result<ReqwestTransport, Error> {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(ReqwestTransport { client })
+    }
+
+    async fn send_reqwest(&self, request: Request) -> Result<reqwest::Response, Error> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| Error::InternalError)?;
+        let mut builder = self.client.request(method, request.url.as_str());
+        for (name, value) in request.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if !request.form.is_empty() {
+            builder = builder.form(&request.form);
+        }
+        builder.send().await.map_err(|err| {
+            // Other failures (eg. timeouts) keep their cause.
+            if err.is_builder() {
+                Error::InvalidRequest(err.to_string())
+            } else if err.is_connect() {
+                Error::UnreachableRemote
+            } else {
+                Error::from(err)
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        let response = self.send_reqwest(request).await?;
+        let status = response.status().as_u16();
+        let headers = response_headers(&response);
+        let body = response.bytes().await?.to_vec();
+        Ok(Response {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    async fn send_streaming(&self, request: Request) -> Result<StreamingResponse, Error> {
+        let response = self.send_reqwest(request).await?;
+        Ok(StreamingResponse {
+            status: response.status().as_u16(),
+            headers: response_headers(&response),
+            body: StreamingBody::Reqwest(response),
+        })
+    }
+}
+
+fn response_headers(response: &reqwest::Response) -> Vec<(String, String)> {
+    response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
@@ -0,0 +1,416 @@
+//! Recording and replaying of intranet traffic, for reproducible runs.
+//!
+//! A `Recorder` wraps another transport and writes every exchange to a cassette file,
+//! which a `Replayer` can later serve back without any network access:
+//!
+//! ```ignore
+//! let recorder = Recorder::new(ReqwestTransport::new()?, "cassette.jsonl");
+//! let client = Client::builder().autologin(link).transport(recorder).authenticate().await?;
+//! // ...
+//! let client = Client::builder().transport(Replayer::load("cassette.jsonl")?).restore(session)?;
+//! ```
+//!
+//! Cassettes are stored as JSON Lines (one interaction per line), and recording only ever appends to them:
+//! remove the file first to record a cassette from scratch.
+//!
+//! They are scrubbed of the cookies, autologin tokens, credentials, the tokens of the Office365 login flow
+//! and of the personal fields of the response bodies, so that they can be shared.
+//! Logins (and other email addresses) are replaced by pseudonyms, everywhere they appear:
+//! a given login always gets the same pseudonym, so the requests built out of replayed responses still match.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::auth;
+use crate::error::Error;
+use crate::transport::{Request, Response, Transport};
+
+/// The placeholder replacing scrubbed values.
+pub static REDACTED: &str = "[REDACTED]";
+
+/// The response fields scrubbed by default, along with everything nested in them.
+static PERSONAL_FIELDS: &[&str] = &[
+    "firstname",
+    "lastname",
+    "nom",
+    "prenom",
+    "internal_email",
+    "userinfo",
+    "autologin",
+    "picture",
+];
+
+/// The domain of the pseudonyms replacing logins and email addresses.
+static PSEUDONYM_DOMAIN: &str = "epitech.eu";
+
+/// The form fields and query parameters scrubbed from recorded requests (and from the hidden inputs of recorded pages),
+/// compared case-insensitively.
+static SECRET_FIELDS: &[&str] = &[
+    "passwd",
+    "password",
+    "token",
+    "flowtoken",
+    "ctx",
+    "canary",
+    "code",
+    "id_token",
+    "state",
+];
+
+/// A recorded request along with the response it received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: Request,
+    pub response: Response,
+}
+
+/// A sequence of recorded interactions, stored as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Cassette, Error> {
+        let contents = fs::read_to_string(path).map_err(|err| Error::IoError(err.to_string()))?;
+        let interactions = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(json::from_str)
+            .collect::<Result<_, _>>()?;
+        Ok(Cassette { interactions })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut contents = String::new();
+        for interaction in self.interactions.iter() {
+            contents.push_str(&json::to_string(interaction)?);
+            contents.push('\n');
+        }
+        fs::write(path, contents).map_err(|err| Error::IoError(err.to_string()))
+    }
+}
+
+/// A transport recording every exchange of the wrapped transport to a cassette file.
+///
+/// Every exchange is appended to the file (which gets created if needed) as soon as it completes,
+/// so that the cassette survives an interrupted run.
+#[derive(Debug)]
+pub struct Recorder {
+    inner: Box<dyn Transport>,
+    path: PathBuf,
+    fields: Vec<String>,
+    cassette: Mutex<Cassette>,
+    file: Mutex<Option<File>>,
+}
+
+impl Recorder {
+    pub fn new<T: Transport + 'static, P: Into<PathBuf>>(inner: T, path: P) -> Recorder {
+        Recorder {
+            inner: Box::new(inner),
+            path: path.into(),
+            fields: PERSONAL_FIELDS
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+            cassette: Mutex::new(Cassette::default()),
+            file: Mutex::default(),
+        }
+    }
+
+    /// Also scrubs the given response field (and everything nested in it) from the cassette.
+    #[inline]
+    pub fn scrub_field<T: Into<String>>(mut self, field: T) -> Recorder {
+        self.fields.push(field.into());
+        self
+    }
+
+    /// The interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Transport for Recorder {
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        let response = self.inner.send(request.clone()).await?;
+        let interaction = Interaction {
+            request: scrub_request(&request),
+            response: scrub_response(&response, &self.fields),
+        };
+        let mut line = json::to_string(&interaction)?;
+        line.push('\n');
+        let mut cassette = self.cassette.lock().unwrap();
+        let mut file = self.file.lock().unwrap();
+        let file = match *file {
+            Some(ref mut file) => file,
+            None => {
+                let opened = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .map_err(io_error)?;
+                file.get_or_insert(opened)
+            }
+        };
+        file.write_all(line.as_bytes()).map_err(io_error)?;
+        cassette.interactions.push(interaction);
+        Ok(response)
+    }
+}
+
+/// A transport serving the interactions of a cassette, without any network access.
+///
+/// Requests are matched on their method, path, query and form.
+/// Identical requests are served the matching interactions in the order they were recorded,
+/// the last one being repeated once they have all been served.
+#[derive(Debug)]
+pub struct Replayer {
+    cassette: Cassette,
+    served: Mutex<Vec<bool>>,
+}
+
+impl Replayer {
+    pub fn new(cassette: Cassette) -> Replayer {
+        let served = vec![false; cassette.interactions.len()];
+        Replayer {
+            cassette,
+            served: Mutex::new(served),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replayer, Error> {
+        Cassette::load(path).map(Replayer::new)
+    }
+}
+
+#[async_trait]
+impl Transport for Replayer {
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        let request = scrub_request(&request);
+        let mut served = self.served.lock().unwrap();
+        let matching: Vec<usize> = self
+            .cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                let recorded = &interaction.request;
+                recorded.method == request.method
+                    && recorded.url == request.url
+                    && recorded.form == request.form
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let index = matching
+            .iter()
+            .find(|index| !served[**index])
+            .or_else(|| matching.last())
+            .copied()
+            .ok_or_else(|| {
+                Error::UnrecordedRequest(format!("{} {}", request.method, request.url))
+            })?;
+        served[index] = true;
+        Ok(self.cassette.interactions[index].response.clone())
+    }
+}
+
+fn io_error(err: std::io::Error) -> Error {
+    Error::IoError(err.to_string())
+}
+
+/// Strips a request down to what identifies it, without any secret:
+/// the URL is made relative to the intranet, the headers (holding the session cookie) are dropped,
+/// and the logins are replaced by their pseudonyms.
+fn scrub_request(request: &Request) -> Request {
+    let url = match reqwest::Url::parse(&request.url) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
+        Err(_) => request.url.clone(),
+    };
+    let url = match url.strip_prefix("/auth-") {
+        Some(_) => format!("/auth-{}", REDACTED),
+        None => pseudonymize(&scrub_query(&url.replace("%40", "@"))),
+    };
+    let form = request
+        .form
+        .iter()
+        .map(|(name, value)| {
+            if is_secret(name) {
+                (name.clone(), String::from(REDACTED))
+            } else {
+                (name.clone(), pseudonymize(value))
+            }
+        })
+        .collect();
+    Request {
+        method: request.method.clone(),
+        url,
+        headers: Vec::default(),
+        form,
+    }
+}
+
+fn scrub_response(response: &Response, fields: &[String]) -> Response {
+    let headers = response
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            if name.eq_ignore_ascii_case("set-cookie") {
+                let end = value.find(';').unwrap_or(value.len());
+                let cookie = value[..end].split('=').next().unwrap_or_default();
+                (
+                    name.clone(),
+                    format!("{}={}{}", cookie, REDACTED, &value[end..]),
+                )
+            } else if name.eq_ignore_ascii_case("location") {
+                (name.clone(), pseudonymize(&scrub_query(value)))
+            } else {
+                (name.clone(), pseudonymize(value))
+            }
+        })
+        .collect();
+    // Binary bodies (eg. downloaded files) are recorded as is.
+    let body = match std::str::from_utf8(&response.body) {
+        Ok(text) => match json::from_str::<json::Value>(text) {
+            Ok(mut value) => {
+                scrub_fields(&mut value, fields);
+                pseudonymize(&value.to_string()).into_bytes()
+            }
+            Err(_) => pseudonymize(&scrub_hidden_inputs(text)).into_bytes(),
+        },
+        Err(_) => response.body.clone(),
+    };
+    Response {
+        status: response.status,
+        headers,
+        body,
+    }
+}
+
+fn is_secret(field: &str) -> bool {
+    SECRET_FIELDS
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(field))
+}
+
+/// Redacts the secret parameters of the query string of a URL (eg. the `code` handed back by Office365).
+fn scrub_query(url: &str) -> String {
+    let (base, query) = match url.find('?') {
+        Some(index) => (&url[..index], &url[index + 1..]),
+        None => return url.to_string(),
+    };
+    let params: Vec<String> = query
+        .split('&')
+        .map(|param| match param.find('=') {
+            Some(index) if is_secret(&param[..index]) => {
+                format!("{}={}", &param[..index], REDACTED)
+            }
+            _ => param.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, params.join("&"))
+}
+
+/// Redacts the values of the secret hidden inputs of a page (eg. the `flowToken` of a login form),
+/// which then get posted back redacted by the replayed flows, matching the recorded requests.
+fn scrub_hidden_inputs(page: &str) -> String {
+    let mut page = page.to_string();
+    for (name, value) in auth::hidden_inputs(&page.clone()) {
+        if !is_secret(&name) || value.is_empty() {
+            continue;
+        }
+        for value in [value.replace('&', "&amp;"), value].iter() {
+            for quote in ["\"", "'", ""].iter() {
+                let attribute = |value: &str| format!("value={}{}{}", quote, value, quote);
+                page = page.replace(&attribute(value), &attribute(REDACTED));
+            }
+        }
+    }
+    page
+}
+
+/// Redacts the strings nested in the given fields, keeping the other values (numbers, flags...)
+/// so that the scrubbed body still deserializes into the same types.
+fn scrub_fields(value: &mut json::Value, fields: &[String]) {
+    match value {
+        json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.iter().any(|field| field == key) {
+                    redact(value);
+                } else {
+                    scrub_fields(value, fields);
+                }
+            }
+        }
+        json::Value::Array(values) => {
+            for value in values.iter_mut() {
+                scrub_fields(value, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact(value: &mut json::Value) {
+    match value {
+        json::Value::String(string) => *string = String::from(REDACTED),
+        json::Value::Object(map) => map.values_mut().for_each(redact),
+        json::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Replaces the email addresses (eg. logins) found in `text` by their pseudonyms.
+fn pseudonymize(text: &str) -> String {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || ".-".contains(c);
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let start = rest[..at]
+            .rfind(|c: char| !is_local(c))
+            .map_or(0, |index| index + 1);
+        let domain = &rest[at + 1..];
+        let domain = domain[..domain.find(|c: char| !is_domain(c)).unwrap_or(domain.len())]
+            .trim_end_matches('.');
+        let end = at + 1 + domain.len();
+        let address = &rest[start..end];
+        output.push_str(&rest[..start]);
+        if start < at && domain.contains('.') && !is_pseudonym(address) {
+            output.push_str(&pseudonym(address));
+        } else {
+            output.push_str(address);
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// The pseudonym of an email address, derived from it using the FNV-1a hash function (stable across runs).
+fn pseudonym(address: &str) -> String {
+    let hash = address
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("student-{:016x}@{}", hash, PSEUDONYM_DOMAIN)
+}
+
+fn is_pseudonym(address: &str) -> bool {
+    let local = address
+        .strip_suffix(PSEUDONYM_DOMAIN)
+        .and_then(|address| address.strip_suffix('@'))
+        .and_then(|local| local.strip_prefix("student-"));
+    local.is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}